@@ -25,11 +25,12 @@ pub struct ApiEndpoints {
 
 /// Assumptions:
 /// 1. All api calls expected to have trailing path "/api"
-///     e.g. "<base_url>/<...>/<...>/api?<query>"
+///    e.g. "<base_url>/<...>/<...>/api?<query>"
 /// 2. First two segments of path of api call expected to be (network, chain)
-///     e.g. "<base_url>/<network>/<chain>/<...>/<...>"
+///    e.g. "<base_url>/<network>/<chain>/<...>/<...>"
+///
 /// Taking it to account, we expect the following api call urls:
-///     e.g. <base_url>/<network>/<chain>/api?<query>   
+///     e.g. <base_url>/<network>/<chain>/api?<query>
 impl From<BlockscoutSettings> for ApiEndpoints {
     fn from(settings: BlockscoutSettings) -> Self {
         let mut apis = Vec::new();
@@ -90,7 +91,7 @@ fn merge_responses(json_responses: Vec<(Instance, String)>) -> Responses {
     json_responses
         .into_iter()
         .for_each(|(Instance(net, subnet), value)| {
-            let kv_subnet = result.entry(net).or_insert_with(HashMap::new);
+            let kv_subnet = result.entry(net).or_default();
             kv_subnet.insert(
                 subnet,
                 serde_json::from_str(&value)
@@ -117,7 +118,7 @@ pub async fn handle_request(
 pub fn run(settings: Settings) -> Result<Server, std::io::Error> {
     let listener = TcpListener::bind(settings.server.addr)?;
 
-    let apis_endpoints: Data<ApiEndpoints> = Data::new(settings.blockscout.try_into().unwrap());
+    let apis_endpoints: Data<ApiEndpoints> = Data::new(settings.blockscout.into());
 
     let server = HttpServer::new(move || {
         App::new()
@@ -19,7 +19,7 @@ async fn check_get_requests() {
         request_timeout: std::time::Duration::from_secs(60),
     };
 
-    let apis_endpoints: ApiEndpoints = settings.try_into().unwrap();
+    let apis_endpoints: ApiEndpoints = settings.into();
 
     let app = test::init_service(
         App::new()
@@ -1,10 +1,107 @@
-use clap::Parser;
+use crate::http_server::handlers::verification::solidity::types::VersionKind;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
-#[derive(Parser)]
+#[derive(Parser, Debug, PartialEq)]
 #[clap(author, version, about, long_about = None)]
 pub struct Args {
-    #[clap(short, long, default_value = "config.toml")]
-    pub config_path: std::path::PathBuf,
+    #[clap(short, long, default_value = "config.toml", global = true)]
+    pub config_path: PathBuf,
+    /// The server is started if no command is specified
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum Command {
+    /// Run the verification server
+    Serve,
+    /// Verify the contract locally and print the verification result as JSON
+    Verify(VerifyArgs),
+    /// Print available compiler versions in descending order
+    Versions(VersionsArgs),
+    /// Download the compilers into the compilers folder in advance
+    Fetch(FetchArgs),
+    /// Write the verified contract into a folder in Sourcify repository layout
+    Export(ExportArgs),
+}
+
+#[derive(clap::Args, Debug, PartialEq)]
+pub struct VerifyArgs {
+    /// Compiler version, in any form accepted by verification requests
+    #[clap(long)]
+    pub compiler_version: String,
+    /// File with hex encoded input of the contract creation transaction
+    #[clap(long)]
+    pub creation_bytecode: PathBuf,
+    /// File with hex encoded bytecode of the deployed contract
+    #[clap(long)]
+    pub deployed_bytecode: PathBuf,
+    /// File with standard JSON input of the compiler, used instead of the source files
+    #[clap(long, conflicts_with_all = &["sources", "evm-version", "optimization-runs", "libraries"])]
+    pub standard_json: Option<PathBuf>,
+    /// Source files compiled together, named by the paths as they are specified
+    #[clap(required_unless_present = "standard-json")]
+    pub sources: Vec<PathBuf>,
+    #[clap(long, default_value = "default")]
+    pub evm_version: String,
+    /// Enables the optimizer with the number of runs
+    #[clap(long)]
+    pub optimization_runs: Option<usize>,
+    /// Address of the library used by the contract, as `<name>=<address>`
+    #[clap(long = "library", parse(try_from_str = parse_library))]
+    pub libraries: Vec<(String, String)>,
+}
+
+#[derive(clap::Args, Debug, PartialEq)]
+pub struct VersionsArgs {
+    /// Print only versions of the kind
+    #[clap(long, possible_values = &["release", "nightly"], parse(try_from_str = parse_version_kind))]
+    pub kind: Option<VersionKind>,
+    /// Print only versions matching the semver requirement (e.g. `^0.8`)
+    #[clap(long)]
+    pub version: Option<String>,
+}
+
+#[derive(clap::Args, Debug, PartialEq)]
+pub struct FetchArgs {
+    /// Versions to download, in any form accepted by verification requests
+    #[clap(required = true)]
+    pub versions: Vec<String>,
+}
+
+#[derive(clap::Args, Debug, PartialEq)]
+pub struct ExportArgs {
+    /// File with the verification result as printed by the `verify` command
+    #[clap(long)]
+    pub result: PathBuf,
+    /// Original `metadata.json` of the contract, written as is; without it the metadata
+    /// is rebuilt from the result and the contract is exported as a partial match
+    #[clap(long)]
+    pub metadata: Option<PathBuf>,
+    /// Id of the chain the contract is deployed to, or its name from `sourcify.chains`
+    #[clap(long)]
+    pub chain: String,
+    /// Address of the deployed contract
+    #[clap(long)]
+    pub address: String,
+    /// Root folder of the repository
+    #[clap(long, default_value = "repository")]
+    pub output: PathBuf,
+}
+
+fn parse_library(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(name, address)| (name.to_string(), address.to_string()))
+        .ok_or_else(|| format!("expected `<name>=<address>`, got `{}`", s))
+}
+
+fn parse_version_kind(s: &str) -> Result<VersionKind, String> {
+    match s {
+        "release" => Ok(VersionKind::Release),
+        "nightly" => Ok(VersionKind::Nightly),
+        _ => Err(format!("expected `release` or `nightly`, got `{}`", s)),
+    }
 }
 
 impl Default for Args {
@@ -12,3 +109,102 @@ impl Default for Args {
         Self::parse()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::try_parse_from(std::iter::once("verification").chain(args.iter().copied()))
+            .unwrap_or_else(|err| panic!("couldn't parse {:?}: {}", args, err))
+    }
+
+    #[test]
+    fn parse_commands() {
+        // running without a command starts the server as before
+        assert_eq!(
+            parse(&["--config-path", "custom.toml"]),
+            Args {
+                config_path: "custom.toml".into(),
+                command: None,
+            }
+        );
+        assert_eq!(
+            parse(&["serve", "-c", "custom.toml"]),
+            Args {
+                config_path: "custom.toml".into(),
+                command: Some(Command::Serve),
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "verify",
+                "--compiler-version",
+                "0.8.14",
+                "--creation-bytecode",
+                "creation.hex",
+                "--deployed-bytecode",
+                "deployed.hex",
+                "--optimization-runs",
+                "200",
+                "--library",
+                "Lib=0x1234",
+                "contracts/A.sol",
+                "contracts/B.sol",
+            ])
+            .command,
+            Some(Command::Verify(VerifyArgs {
+                compiler_version: "0.8.14".into(),
+                creation_bytecode: "creation.hex".into(),
+                deployed_bytecode: "deployed.hex".into(),
+                standard_json: None,
+                sources: vec!["contracts/A.sol".into(), "contracts/B.sol".into()],
+                evm_version: "default".into(),
+                optimization_runs: Some(200),
+                libraries: vec![("Lib".into(), "0x1234".into())],
+            }))
+        );
+        assert_eq!(
+            parse(&["versions", "--kind", "release", "--version", "^0.8"]).command,
+            Some(Command::Versions(VersionsArgs {
+                kind: Some(VersionKind::Release),
+                version: Some("^0.8".into()),
+            }))
+        );
+        assert_eq!(
+            parse(&["fetch", "0.8.14", "latest"]).command,
+            Some(Command::Fetch(FetchArgs {
+                versions: vec!["0.8.14".into(), "latest".into()],
+            }))
+        );
+    }
+
+    #[test]
+    fn reject_invalid_commands() {
+        let verify = [
+            "verify",
+            "--compiler-version",
+            "0.8.14",
+            "--creation-bytecode",
+            "creation.hex",
+            "--deployed-bytecode",
+            "deployed.hex",
+        ];
+        for (args, reason) in [
+            (vec![], "neither source files nor standard JSON"),
+            (
+                vec!["--standard-json", "input.json", "A.sol"],
+                "both source files and standard JSON",
+            ),
+            (vec!["--library", "Lib", "A.sol"], "library without address"),
+        ] {
+            let args = std::iter::once("verification").chain(verify.into_iter().chain(args));
+            assert!(Args::try_parse_from(args).is_err(), "{}", reason);
+        }
+        for args in [&["fetch"][..], &["versions", "--kind", "unknown"]] {
+            let parsed =
+                Args::try_parse_from(std::iter::once("verification").chain(args.iter().copied()));
+            assert!(parsed.is_err(), "{:?}", args);
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! Commands verifying contracts and managing compilers without starting the server.
+
+use crate::{
+    cli::{Args, Command, ExportArgs, FetchArgs, VerifyArgs, VersionsArgs},
+    compiler::Compilers,
+    http_server::handlers::{
+        admin::prefetch_versions,
+        multi_part::verify_multi_part,
+        sourcify::export::repository_files,
+        standard_json::verify_standard_json,
+        verification::{
+            solidity::types::{MultiPartFiles, StandardJson, VerificationRequest, VersionsQuery},
+            VerificationStatus,
+        },
+        version_list::list_versions,
+    },
+    server,
+    solidity::AnyFetcher,
+    Config, VerificationResult,
+};
+use anyhow::Context;
+use std::{io::Write, path::Path};
+
+/// Parses the arguments and the configuration and runs the command.
+pub async fn run_cli() -> anyhow::Result<()> {
+    let args = Args::default();
+    let config = Config::from_file(args.config_path).expect("Failed to parse config");
+    run_command(config, args.command, &mut std::io::stdout()).await
+}
+
+/// Runs the command, writing its output into `out`.
+/// Failed verifications and downloads are returned as errors.
+async fn run_command(
+    config: Config,
+    command: Option<Command>,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    match command.unwrap_or(Command::Serve) {
+        Command::Serve => server::run(config).await,
+        Command::Verify(args) => verify(config, args, out).await,
+        Command::Versions(args) => versions(config, args, out).await,
+        Command::Fetch(args) => fetch(config, args, out).await,
+        Command::Export(args) => export(config, args, out),
+    }
+}
+
+async fn compilers(config: &Config) -> Compilers<AnyFetcher> {
+    Compilers::new(AnyFetcher::from_config(&config.solidity).await)
+}
+
+fn read_file(path: &Path) -> anyhow::Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("couldn't read {:?}", path))
+}
+
+async fn verify(config: Config, args: VerifyArgs, out: &mut impl Write) -> anyhow::Result<()> {
+    let creation_bytecode = read_file(&args.creation_bytecode)?.trim().to_string();
+    let deployed_bytecode = read_file(&args.deployed_bytecode)?.trim().to_string();
+    let compilers = compilers(&config).await;
+
+    let response = match args.standard_json {
+        Some(path) => {
+            let input = serde_json::from_str(&read_file(&path)?)
+                .with_context(|| format!("{:?} is not a valid standard JSON input", path))?;
+            let request = VerificationRequest {
+                deployed_bytecode,
+                creation_bytecode,
+                compiler_version: args.compiler_version,
+                content: StandardJson { input },
+            };
+            verify_standard_json(&compilers, request).await
+        }
+        None => {
+            let sources = args
+                .sources
+                .into_iter()
+                .map(|path| Ok((path.clone(), read_file(&path)?)))
+                .collect::<anyhow::Result<_>>()?;
+            let contract_libraries =
+                (!args.libraries.is_empty()).then(|| args.libraries.into_iter().collect());
+            let request = VerificationRequest {
+                deployed_bytecode,
+                creation_bytecode,
+                compiler_version: args.compiler_version,
+                content: MultiPartFiles {
+                    sources,
+                    evm_version: args.evm_version,
+                    optimization_runs: args.optimization_runs,
+                    contract_libraries,
+                },
+            };
+            verify_multi_part(&compilers, request).await
+        }
+    }?;
+
+    match (response.status, response.result) {
+        (VerificationStatus::Ok, Some(result)) => {
+            serde_json::to_writer_pretty(&mut *out, &result)?;
+            writeln!(out)?;
+            Ok(())
+        }
+        _ => anyhow::bail!("verification failed: {}", response.message),
+    }
+}
+
+async fn versions(config: Config, args: VersionsArgs, out: &mut impl Write) -> anyhow::Result<()> {
+    let compilers = compilers(&config).await;
+    let query = VersionsQuery {
+        kind: args.kind,
+        version: args.version,
+        metadata: false,
+    };
+    for version in list_versions(&compilers, &query).await?.versions {
+        writeln!(out, "{}", version)?;
+    }
+    Ok(())
+}
+
+async fn fetch(config: Config, args: FetchArgs, out: &mut impl Write) -> anyhow::Result<()> {
+    let compilers = compilers(&config).await;
+    let response = prefetch_versions(&compilers, &args.versions).await;
+    for (requested, version) in &response.downloaded {
+        writeln!(out, "{}: {}", requested, version)?;
+    }
+    for (requested, err) in &response.failed {
+        log::error!("couldn't fetch compiler {}: {}", requested, err);
+    }
+    if !response.failed.is_empty() {
+        anyhow::bail!("{} compiler(s) couldn't be fetched", response.failed.len());
+    }
+    Ok(())
+}
+
+fn export(config: Config, args: ExportArgs, out: &mut impl Write) -> anyhow::Result<()> {
+    let result: VerificationResult = serde_json::from_str(&read_file(&args.result)?)
+        .with_context(|| format!("{:?} is not a valid verification result", args.result))?;
+    let chain_id = match args.chain.parse() {
+        Ok(chain_id) => chain_id,
+        Err(_) => *config
+            .sourcify
+            .chains
+            .get(&args.chain)
+            .with_context(|| format!("unknown chain '{}'", args.chain))?,
+    };
+    let metadata = args.metadata.as_deref().map(read_file).transpose()?;
+    for (path, content) in repository_files(&result, metadata.as_deref(), chain_id, &args.address)?
+    {
+        let path = args.output.join(path);
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder)
+                .with_context(|| format!("couldn't create {:?}", folder))?;
+        }
+        std::fs::write(&path, content).with_context(|| format!("couldn't write {:?}", path))?;
+        writeln!(out, "{}", path.display())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VersionKind;
+    use serde_json::json;
+    use std::env::temp_dir;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    /// Hash of the "compiler" string all the compilers are mocked with
+    const COMPILER_SHA256: &str =
+        "0xe996bb0ea465fae70d3e3c66b3b6e02d33d2f1eb76d5958720578b6cf359cc2e";
+
+    async fn test_config(folder: &str) -> (MockServer, Config) {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "builds": [
+                    {
+                        "path": "solc-v0.8.15-nightly",
+                        "longVersion": "0.8.15-nightly.2022.5.27+commit.095cc647",
+                        "sha256": COMPILER_SHA256
+                    },
+                    {
+                        "path": "solc-v0.8.14",
+                        "longVersion": "0.8.14+commit.80d49f37",
+                        "sha256": COMPILER_SHA256
+                    },
+                    {
+                        "path": "solc-v0.7.6",
+                        "longVersion": "0.7.6+commit.7338295f",
+                        "sha256": COMPILER_SHA256
+                    }
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/solc-v0.8.14"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes("compiler"))
+            .mount(&mock_server)
+            .await;
+
+        let folder = temp_dir().join("blockscout/verification/cli").join(folder);
+        let _ = std::fs::remove_dir_all(&folder);
+        let mut config = Config::default();
+        config.solidity.compilers_folder = folder;
+        config.solidity.compilers_lists[0].url =
+            format!("{}/list.json", mock_server.uri()).parse().unwrap();
+        (mock_server, config)
+    }
+
+    async fn run(config: Config, command: Command) -> anyhow::Result<String> {
+        let mut out = vec![];
+        run_command(config, Some(command), &mut out).await?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[tokio::test]
+    async fn prints_versions() {
+        for (kind, version, expected) in [
+            (
+                None,
+                None,
+                "v0.8.15-nightly.2022.5.27+commit.095cc647\nv0.8.14+commit.80d49f37\nv0.7.6+commit.7338295f\n",
+            ),
+            (
+                Some(VersionKind::Release),
+                Some("^0.8"),
+                "v0.8.14+commit.80d49f37\n",
+            ),
+        ] {
+            let (_mock_server, config) = test_config("versions").await;
+            let args = VersionsArgs {
+                kind,
+                version: version.map(str::to_string),
+            };
+            let output = run(config, Command::Versions(args)).await.unwrap();
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_compilers() {
+        let (_mock_server, config) = test_config("fetch").await;
+        let folder = config.solidity.compilers_folder.clone();
+        let args = FetchArgs {
+            versions: vec!["0.8.14".into()],
+        };
+        let output = run(config, Command::Fetch(args)).await.unwrap();
+        assert_eq!(output, "0.8.14: v0.8.14+commit.80d49f37\n");
+        assert_eq!(
+            std::fs::read(folder.join("v0.8.14+commit.80d49f37/solc")).unwrap(),
+            b"compiler"
+        );
+
+        // the compiler of 0.7.6 is not served
+        let (_mock_server, config) = test_config("fetch").await;
+        let args = FetchArgs {
+            versions: vec!["0.8.14".into(), "0.7.6".into()],
+        };
+        let err = run(config, Command::Fetch(args))
+            .await
+            .expect_err("fetch should fail");
+        assert_eq!(err.to_string(), "1 compiler(s) couldn't be fetched");
+    }
+
+    #[tokio::test]
+    async fn reports_invalid_verification_requests() {
+        let (_mock_server, config) = test_config("verify").await;
+        let folder = config.solidity.compilers_folder.clone();
+        std::fs::create_dir_all(&folder).unwrap();
+        let bytecode = folder.join("bytecode.hex");
+        std::fs::write(&bytecode, "0x6001\n").unwrap();
+        let source = folder.join("source.sol");
+        std::fs::write(&source, "pragma solidity ^0.8.0;").unwrap();
+
+        let args = |compiler_version: &str, sources| VerifyArgs {
+            compiler_version: compiler_version.into(),
+            creation_bytecode: bytecode.clone(),
+            deployed_bytecode: bytecode.clone(),
+            standard_json: None,
+            sources,
+            evm_version: "default".into(),
+            optimization_runs: None,
+            libraries: vec![],
+        };
+        let err = run(
+            config.clone(),
+            Command::Verify(args("0.6.0", vec![source.clone()])),
+        )
+        .await
+        .expect_err("unknown version");
+        assert!(err.to_string().contains("0.6.0"), "{}", err);
+
+        let err = run(
+            config,
+            Command::Verify(args("0.8.14", vec![folder.join("missing.sol")])),
+        )
+        .await
+        .expect_err("missing source file");
+        assert!(err.to_string().contains("missing.sol"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn exports_verification_results() {
+        let folder = temp_dir().join("blockscout/verification/cli/export");
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let result = folder.join("result.json");
+        std::fs::write(
+            &result,
+            json!({
+                "file_name": "contracts/A.sol",
+                "contract_name": "A",
+                "compiler_version": "v0.8.14+commit.80d49f37",
+                "evm_version": "default",
+                "constructor_arguments": "0xcafe",
+                "optimization": false,
+                "optimization_runs": null,
+                "contract_libraries": {},
+                "abi": "[]",
+                "sources": { "contracts/A.sol": "contract A {}" },
+                "match_type": "perfect",
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let args = |chain: &str| ExportArgs {
+            result: result.clone(),
+            metadata: None,
+            chain: chain.into(),
+            address: "0xcafecafecafecafecafecafecafecafecafecafe".into(),
+            output: folder.join("repository"),
+        };
+        // the chain is specified by its name from the configuration
+        let output = run(Config::default(), Command::Export(args("xdai/mainnet")))
+            .await
+            .unwrap();
+        // the metadata is rebuilt, so even the perfect match is exported as a partial one
+        let contract = folder.join(
+            "repository/contracts/partial_match/100/0xCAfEcAfeCAfECaFeCaFecaFecaFECafECafeCaFe",
+        );
+        assert_eq!(output.lines().count(), 3, "{}", output);
+        assert_eq!(
+            std::fs::read_to_string(contract.join("sources/contracts/A.sol")).unwrap(),
+            "contract A {}"
+        );
+        assert_eq!(
+            std::fs::read_to_string(contract.join("constructor-args.txt")).unwrap(),
+            "0xcafe"
+        );
+        let metadata: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(contract.join("metadata.json")).unwrap())
+                .unwrap();
+        assert_eq!(metadata["compiler"]["version"], "0.8.14+commit.80d49f37");
+        assert_eq!(
+            metadata["settings"]["compilationTarget"],
+            json!({ "contracts/A.sol": "A" })
+        );
+
+        // the original metadata is written as is into the full match folder
+        let original = folder.join("metadata.json");
+        let metadata = json!({
+            "compiler": { "version": "0.8.14+commit.80d49f37" },
+            "language": "Solidity",
+            "output": { "abi": [] },
+            "settings": {
+                "compilationTarget": { "contracts/A.sol": "A" },
+                "metadata": { "bytecodeHash": "ipfs" },
+                "optimizer": { "enabled": false, "runs": 200 },
+                "remappings": [],
+            },
+            "sources": {
+                "contracts/A.sol": {
+                    "keccak256": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                    "urls": ["dweb:/ipfs/Qm"],
+                },
+            },
+            "version": 1,
+        })
+        .to_string();
+        std::fs::write(&original, &metadata).unwrap();
+        let mut full_match_args = args("100");
+        full_match_args.metadata = Some(original);
+        run(Config::default(), Command::Export(full_match_args))
+            .await
+            .unwrap();
+        let contract = folder
+            .join("repository/contracts/full_match/100/0xCAfEcAfeCAfECaFeCaFecaFecaFECafECafeCaFe");
+        assert_eq!(
+            std::fs::read_to_string(contract.join("metadata.json")).unwrap(),
+            metadata
+        );
+
+        let err = run(Config::default(), Command::Export(args("unknown")))
+            .await
+            .expect_err("chain is unknown");
+        assert!(err.to_string().contains("unknown"), "{}", err);
+    }
+}
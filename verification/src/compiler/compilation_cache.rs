@@ -0,0 +1,437 @@
+use super::version::CompilerVersion;
+use ethers_core::utils::keccak256;
+use ethers_solc::{CompilerInput, CompilerOutput};
+use primitive_types::H256;
+use std::{
+    collections::HashMap,
+    future::Future,
+    io::Write,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+/// Content-addressed cache of compilation outputs.
+///
+/// Outputs are keyed by the hash of the compiler version and the compiler input,
+/// so the same input compiled with the same compiler is never compiled twice
+/// while the corresponding entry stays in the cache.
+/// The in-memory part is bounded by the total size of serialized outputs
+/// (in bytes) and evicts the least recently used ones. If `folder` is specified,
+/// outputs are also persisted on disk and survive the service restarts;
+/// the folder is bounded by `max_folder_size` (in bytes) the same way.
+pub struct CompilationCache {
+    max_size: NonZeroUsize,
+    folder: Option<PathBuf>,
+    max_folder_size: NonZeroUsize,
+    entries: parking_lot::Mutex<Entries>,
+    /// Locks of the keys being compiled, so that concurrent misses compile the input once
+    in_flight: parking_lot::Mutex<HashMap<H256, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+struct Entry {
+    output: Arc<CompilerOutput>,
+    /// Size of the serialized output in bytes
+    size: usize,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    values: HashMap<H256, Entry>,
+    /// Total size of the stored outputs
+    size: usize,
+    /// Monotonically increasing counter used to track the last access to the entries
+    clock: u64,
+}
+
+impl Entries {
+    fn get(&mut self, key: &H256) -> Option<Arc<CompilerOutput>> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.values.get_mut(key).map(|entry| {
+            entry.last_used = clock;
+            entry.output.clone()
+        })
+    }
+
+    fn insert(
+        &mut self,
+        key: H256,
+        output: Arc<CompilerOutput>,
+        size: usize,
+        max_size: NonZeroUsize,
+    ) {
+        if let Some(previous) = self.values.remove(&key) {
+            self.size -= previous.size;
+        }
+        // the output would evict everything else and still not fit
+        if size > max_size.get() {
+            return;
+        }
+        self.clock += 1;
+        let entry = Entry {
+            output,
+            size,
+            last_used: self.clock,
+        };
+        self.values.insert(key, entry);
+        self.size += size;
+        while self.size > max_size.get() {
+            let least_recently_used = self
+                .values
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+                .expect("cache is not empty");
+            let evicted = self
+                .values
+                .remove(&least_recently_used)
+                .expect("key is taken from the cache");
+            self.size -= evicted.size;
+        }
+    }
+}
+
+/// Lock of the key being compiled, removed from the cache once nobody waits for it.
+struct InFlight<'a> {
+    cache: &'a CompilationCache,
+    key: H256,
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.cache.in_flight.lock();
+        // the map and this guard hold the only references
+        if Arc::strong_count(&self.lock) == 2 {
+            in_flight.remove(&self.key);
+        }
+    }
+}
+
+impl CompilationCache {
+    pub fn new(
+        max_size: NonZeroUsize,
+        folder: Option<PathBuf>,
+        max_folder_size: NonZeroUsize,
+    ) -> Self {
+        Self {
+            max_size,
+            folder,
+            max_folder_size,
+            entries: Default::default(),
+            in_flight: Default::default(),
+        }
+    }
+
+    /// Calculates the key the compilation output is stored under.
+    pub fn key(compiler_version: &CompilerVersion, input: &CompilerInput) -> H256 {
+        let input = serde_json::to_vec(&(compiler_version.to_string(), input))
+            .expect("compiler input should be serializable");
+        H256::from(keccak256(input))
+    }
+
+    /// Returns the cached output or caches the one returned by `compile`.
+    /// Concurrent calls with the same key wait for the first one to finish
+    /// instead of compiling the same input again.
+    pub async fn get_or_compile<E>(
+        &self,
+        key: H256,
+        compile: impl Future<Output = Result<CompilerOutput, E>>,
+    ) -> Result<Arc<CompilerOutput>, E> {
+        if let Some(output) = self.get(&key).await {
+            return Ok(output);
+        }
+        let in_flight = InFlight {
+            cache: self,
+            key,
+            lock: Arc::clone(self.in_flight.lock().entry(key).or_default()),
+        };
+        let _guard = in_flight.lock.lock().await;
+        // the output could have been compiled while waiting for the lock
+        if let Some(output) = self.get(&key).await {
+            return Ok(output);
+        }
+        let output = Arc::new(compile.await?);
+        self.insert(key, output.clone()).await;
+        Ok(output)
+    }
+
+    pub async fn get(&self, key: &H256) -> Option<Arc<CompilerOutput>> {
+        if let Some(output) = self.entries.lock().get(key) {
+            log::debug!(target: "compilation_cache", "in-memory cache hit for {:x}", key);
+            return Some(output);
+        }
+
+        let file = self.file(key)?;
+        let (output, size) = tokio::task::spawn_blocking(move || read_output(&file))
+            .await
+            .ok()??;
+        log::debug!(target: "compilation_cache", "on-disk cache hit for {:x}", key);
+        let output = Arc::new(output);
+        self.entries
+            .lock()
+            .insert(*key, output.clone(), size, self.max_size);
+        Some(output)
+    }
+
+    pub async fn insert(&self, key: H256, output: Arc<CompilerOutput>) {
+        let content = match serialize(output.clone()).await {
+            Ok(content) => content,
+            Err(err) => {
+                log::warn!(target: "compilation_cache", "cannot serialize compilation output {:x}: {:#}", key, err);
+                return;
+            }
+        };
+        self.entries
+            .lock()
+            .insert(key, output, content.len(), self.max_size);
+
+        if let Some(file) = self.file(&key) {
+            let max_folder_size = self.max_folder_size.get();
+            if let Err(err) = persist(file, content, max_folder_size).await {
+                log::warn!(target: "compilation_cache", "cannot persist compilation output {:x}: {:#}", key, err);
+            }
+        }
+    }
+
+    fn file(&self, key: &H256) -> Option<PathBuf> {
+        self.folder
+            .as_ref()
+            .map(|folder| folder.join(format!("{:x}.json", key)))
+    }
+}
+
+/// Reads the output together with its serialized size.
+/// The file is marked as recently used, so that it is evicted from the folder last.
+fn read_output(file: &Path) -> Option<(CompilerOutput, usize)> {
+    let content = std::fs::read(file).ok()?;
+    let touched = std::fs::File::options()
+        .write(true)
+        .open(file)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    if let Err(err) = touched {
+        log::debug!(target: "compilation_cache", "cannot update modification time of {:?}: {}", file, err);
+    }
+    match serde_json::from_slice(&content) {
+        Ok(output) => Some((output, content.len())),
+        Err(err) => {
+            log::warn!(target: "compilation_cache", "invalid cached output at {:?}: {}", file, err);
+            None
+        }
+    }
+}
+
+async fn serialize(output: Arc<CompilerOutput>) -> anyhow::Result<Vec<u8>> {
+    Ok(tokio::task::spawn_blocking(move || serde_json::to_vec(&*output)).await??)
+}
+
+async fn persist(file: PathBuf, content: Vec<u8>, max_folder_size: usize) -> anyhow::Result<()> {
+    tokio::task::spawn_blocking(move || {
+        write_output(&file, &content)?;
+        let folder = file
+            .parent()
+            .expect("cache file always has a parent folder");
+        prune_folder(folder, max_folder_size)
+    })
+    .await?
+}
+
+fn write_output(file: &Path, content: &[u8]) -> anyhow::Result<()> {
+    let folder = file
+        .parent()
+        .expect("cache file always has a parent folder");
+    std::fs::create_dir_all(folder)?;
+    // write into a unique temporary file first, so that concurrent writers
+    // of the same key don't interfere and readers never see partially written outputs
+    let mut tmp = tempfile::NamedTempFile::new_in(folder)?;
+    tmp.write_all(content)?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(file)?;
+    Ok(())
+}
+
+/// Removes the least recently used outputs until the total size of the folder fits `max_size`.
+fn prune_folder(folder: &Path, max_size: usize) -> anyhow::Result<()> {
+    let mut files = Vec::new();
+    let mut total_size = 0;
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+            continue;
+        }
+        // the file may be removed by a concurrent pruning
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        let size = metadata.len() as usize;
+        total_size += size;
+        files.push((metadata.modified()?, size, path));
+    }
+    if total_size <= max_size {
+        return Ok(());
+    }
+
+    files.sort();
+    for (_, size, path) in files {
+        if total_size <= max_size {
+            break;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                log::debug!(target: "compilation_cache", "evicted {:?} from the folder", path)
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        total_size -= size;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::version::ReleaseVersion;
+    use ethers_solc::artifacts::{Source, Sources};
+
+    fn new_version(major: u64) -> CompilerVersion {
+        CompilerVersion::Release(ReleaseVersion {
+            version: semver::Version::new(major, 0, 0),
+            commit: [0, 1, 2, 3],
+        })
+    }
+
+    fn new_input(content: &str) -> CompilerInput {
+        CompilerInput {
+            language: "Solidity".to_string(),
+            sources: Sources::from([(
+                "source.sol".into(),
+                Source {
+                    content: content.to_string(),
+                },
+            )]),
+            settings: Default::default(),
+        }
+    }
+
+    fn new_output(source: &str) -> Arc<CompilerOutput> {
+        let mut output = CompilerOutput::default();
+        output
+            .contracts
+            .insert(source.to_string(), Default::default());
+        Arc::new(output)
+    }
+
+    #[test]
+    fn key_depends_on_version_and_input() {
+        let key = CompilationCache::key;
+        assert_eq!(
+            key(&new_version(1), &new_input("a")),
+            key(&new_version(1), &new_input("a"))
+        );
+        assert_ne!(
+            key(&new_version(1), &new_input("a")),
+            key(&new_version(2), &new_input("a"))
+        );
+        assert_ne!(
+            key(&new_version(1), &new_input("a")),
+            key(&new_version(1), &new_input("b"))
+        );
+    }
+
+    fn output_size(output: &CompilerOutput) -> usize {
+        serde_json::to_vec(output).unwrap().len()
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        // outputs of the same size, so that only two of them fit
+        let max_size = NonZeroUsize::new(2 * output_size(&new_output("0"))).unwrap();
+        let cache = CompilationCache::new(max_size, None, max_size);
+        let keys: Vec<_> = (0..3).map(H256::from_low_u64_be).collect();
+
+        cache.insert(keys[0], new_output("0")).await;
+        cache.insert(keys[1], new_output("1")).await;
+        // touch the first entry, so that the second one becomes the least recently used
+        assert_eq!(cache.get(&keys[0]).await, Some(new_output("0")));
+        cache.insert(keys[2], new_output("2")).await;
+
+        assert_eq!(cache.get(&keys[0]).await, Some(new_output("0")));
+        assert_eq!(cache.get(&keys[1]).await, None);
+        assert_eq!(cache.get(&keys[2]).await, Some(new_output("2")));
+    }
+
+    #[tokio::test]
+    async fn skips_outputs_larger_than_max_size() {
+        let output = new_output("large");
+        let max_size = NonZeroUsize::new(output_size(&output) - 1).unwrap();
+        let cache = CompilationCache::new(max_size, None, max_size);
+        let key = H256::from_low_u64_be(0);
+
+        cache.insert(key, output).await;
+        assert_eq!(cache.get(&key).await, None);
+    }
+
+    #[tokio::test]
+    async fn persists_on_disk() {
+        let folder = tempfile::tempdir().unwrap();
+        let key = CompilationCache::key(&new_version(1), &new_input("persists_on_disk"));
+        let max_size = NonZeroUsize::new(1024 * 1024).unwrap();
+
+        let cache = CompilationCache::new(max_size, Some(folder.path().to_path_buf()), max_size);
+        cache.insert(key, new_output("persisted")).await;
+
+        // new cache instance has an empty in-memory part
+        let cache = CompilationCache::new(max_size, Some(folder.path().to_path_buf()), max_size);
+        assert_eq!(cache.get(&key).await, Some(new_output("persisted")));
+    }
+
+    #[tokio::test]
+    async fn compiles_concurrent_misses_once() {
+        let max_size = NonZeroUsize::new(1024 * 1024).unwrap();
+        let cache = CompilationCache::new(max_size, None, max_size);
+        let key = H256::from_low_u64_be(0);
+        let compilations = std::sync::atomic::AtomicUsize::new(0);
+        let compile = || async {
+            compilations.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            Ok::<_, ()>(CompilerOutput::clone(&new_output("compiled")))
+        };
+
+        let (first, second) = futures::join!(
+            cache.get_or_compile(key, compile()),
+            cache.get_or_compile(key, compile())
+        );
+        assert!(Arc::ptr_eq(&first.unwrap(), &second.unwrap()));
+        assert_eq!(compilations.into_inner(), 1);
+        assert!(cache.in_flight.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn bounds_folder_size() {
+        let folder = tempfile::tempdir().unwrap();
+        let max_size = NonZeroUsize::new(1024 * 1024).unwrap();
+        // outputs of the same size, so that only two of them fit into the folder
+        let max_folder_size = NonZeroUsize::new(2 * output_size(&new_output("0"))).unwrap();
+        let cache =
+            CompilationCache::new(max_size, Some(folder.path().to_path_buf()), max_folder_size);
+        let keys: Vec<_> = (0..3).map(H256::from_low_u64_be).collect();
+
+        for (i, key) in keys.iter().enumerate() {
+            cache.insert(*key, new_output(&i.to_string())).await;
+            // modification times of the files differ
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let file = |key: &H256| cache.file(key).unwrap();
+        assert!(
+            !file(&keys[0]).exists(),
+            "the oldest output should be evicted"
+        );
+        assert!(file(&keys[1]).exists());
+        assert!(file(&keys[2]).exists());
+    }
+}
@@ -1,8 +1,21 @@
-use crate::compiler::{CompilerVersion, DownloadCache, Fetcher, VersionList};
+use crate::{
+    compiler::{CompilationCache, CompilerVersion, DownloadCache, Fetcher, VersionList},
+    metrics,
+};
 use anyhow::anyhow;
-use ethers_solc::{artifacts::Severity, error::SolcError, CompilerInput, CompilerOutput, Solc};
-use std::fmt::{Debug, Display};
+use ethers_solc::{
+    artifacts::Severity,
+    error::{SolcError, SolcIoError},
+    CompilerInput, CompilerOutput,
+};
+use std::{
+    fmt::{Debug, Display},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+};
 use thiserror::Error;
+use tokio::{io::AsyncWriteExt, sync::watch};
 
 #[derive(Debug, Error)]
 pub enum CompilersError {
@@ -12,36 +25,98 @@ pub enum CompilersError {
     Internal(#[from] SolcError),
     #[error("Compilation error: {0:?}")]
     Compilation(Vec<String>),
+    #[error("Compilation cancelled as the service is shutting down")]
+    Cancelled,
 }
 
 pub struct Compilers<T> {
     cache: DownloadCache,
+    compilation_cache: Option<CompilationCache>,
     fetcher: T,
+    cancel: watch::Sender<bool>,
 }
 
 impl<T: Fetcher> Compilers<T> {
     pub fn new(fetcher: T) -> Self {
         Self {
             cache: DownloadCache::new(),
+            compilation_cache: None,
             fetcher,
+            cancel: watch::channel(false).0,
         }
     }
 
+    /// Enables caching of compilation outputs, so that compilation
+    /// of already compiled inputs returns the previously obtained output.
+    pub fn with_compilation_cache(mut self, compilation_cache: CompilationCache) -> Self {
+        self.compilation_cache = Some(compilation_cache);
+        self
+    }
+
+    pub fn fetcher(&self) -> &T {
+        &self.fetcher
+    }
+
+    /// Checks whether the compiler is available locally without downloading it.
+    pub fn is_downloaded(&self, compiler_version: &CompilerVersion) -> bool {
+        self.cache.is_downloaded(compiler_version)
+    }
+
+    /// Returns already downloaded compilers with paths to them.
+    pub fn downloaded(&self) -> Vec<(CompilerVersion, PathBuf)> {
+        self.cache.downloaded()
+    }
+
+    /// Downloads the compiler in advance, so that compilations don't have to wait for it.
+    pub async fn prefetch(&self, compiler_version: &CompilerVersion) -> Result<(), CompilersError>
+    where
+        <T as Fetcher>::Error: Debug + Display,
+    {
+        self.cache
+            .get(&self.fetcher, compiler_version)
+            .await
+            .map_err(|err| CompilersError::Fetch(anyhow!(err)))?;
+        Ok(())
+    }
+
+    /// Removes the compiler from the cache and deletes its file.
+    /// Returns `false` if the compiler has not been downloaded.
+    pub async fn evict(&self, compiler_version: &CompilerVersion) -> std::io::Result<bool> {
+        match self.cache.evict(compiler_version).await {
+            Some(file) => {
+                tokio::task::spawn_blocking(move || std::fs::remove_file(file))
+                    .await
+                    .map_err(std::io::Error::other)??;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Kills running solc processes and rejects further compilations.
+    /// Used on shutdown, after running requests had a chance to finish.
+    pub fn cancel_compilations(&self) {
+        let _ = self.cancel.send(true);
+    }
+
+    /// Compiles the input, returning the output shared with the compilation cache if enabled.
     pub async fn compile(
         &self,
         compiler_version: &CompilerVersion,
         input: &CompilerInput,
-    ) -> Result<CompilerOutput, CompilersError>
+    ) -> Result<Arc<CompilerOutput>, CompilersError>
     where
         <T as Fetcher>::Error: Debug + Display,
     {
-        let solc_path = self
-            .cache
-            .get(&self.fetcher, compiler_version)
-            .await
-            .map_err(|err| CompilersError::Fetch(anyhow!(err)))?;
-        let solc = Solc::from(solc_path);
-        let output = solc.compile(&input)?;
+        let output = match &self.compilation_cache {
+            Some(compilation_cache) => {
+                let key = CompilationCache::key(compiler_version, input);
+                compilation_cache
+                    .get_or_compile(key, self.compile_with_solc(compiler_version, input))
+                    .await?
+            }
+            None => Arc::new(self.compile_with_solc(compiler_version, input).await?),
+        };
 
         // Compilations errors, warnings and info messages are returned in `CompilerOutput.error`
         let mut errors = Vec::new();
@@ -61,6 +136,58 @@ impl<T: Fetcher> Compilers<T> {
 
         Ok(output)
     }
+
+    async fn compile_with_solc(
+        &self,
+        compiler_version: &CompilerVersion,
+        input: &CompilerInput,
+    ) -> Result<CompilerOutput, CompilersError>
+    where
+        <T as Fetcher>::Error: Debug + Display,
+    {
+        let mut cancelled = self.cancel.subscribe();
+        if *cancelled.borrow() {
+            return Err(CompilersError::Cancelled);
+        }
+        let solc_path = self
+            .cache
+            .get(&self.fetcher, compiler_version)
+            .await
+            .map_err(|err| CompilersError::Fetch(anyhow!(err)))?;
+        let _timer = metrics::COMPILATION_TIME
+            .with_label_values(&[&compiler_version.to_string()])
+            .start_timer();
+        tokio::select! {
+            output = run_solc(&solc_path, input) => Ok(output?),
+            _ = cancelled.changed() => Err(CompilersError::Cancelled),
+        }
+    }
+}
+
+/// Runs `solc --standard-json` as [`Solc::compile`](ethers_solc::Solc::compile) does,
+/// but kills the process if the returned future is dropped before it finishes.
+async fn run_solc(solc: &Path, input: &CompilerInput) -> Result<CompilerOutput, SolcError> {
+    let io_error = |err| SolcError::Io(SolcIoError::new(err, solc));
+    let content = serde_json::to_vec(input)?;
+    let mut child = tokio::process::Command::new(solc)
+        .arg("--standard-json")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(io_error)?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(&content).await.map_err(io_error)?;
+    // solc starts compiling once the input is closed
+    drop(stdin);
+    let output = child.wait_with_output().await.map_err(io_error)?;
+    if !output.status.success() {
+        return Err(SolcError::SolcError(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
 }
 
 impl<T: VersionList> VersionList for Compilers<T> {
@@ -72,24 +199,29 @@ impl<T: VersionList> VersionList for Compilers<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::solidity::CompilerFetcher;
+    use crate::solidity::{CompilerFetcher, ListTimeouts};
     use std::{env::temp_dir, str::FromStr};
 
     use crate::consts::DEFAULT_COMPILER_LIST;
     use async_once_cell::OnceCell;
     use ethers_solc::artifacts::{Source, Sources};
     use std::default::Default;
+    use url::Url;
 
     async fn global_compilers() -> &'static Compilers<CompilerFetcher> {
         static COMPILERS: OnceCell<Compilers<CompilerFetcher>> = OnceCell::new();
         COMPILERS
             .get_or_init(async {
-                let url = DEFAULT_COMPILER_LIST.try_into().expect("Getting url");
-                let fetcher = CompilerFetcher::new(url, None, temp_dir())
-                    .await
-                    .expect("Fetch releases");
-                let compilers = Compilers::new(fetcher);
-                compilers
+                let url = Url::try_from(DEFAULT_COMPILER_LIST).expect("Getting url");
+                let fetcher = CompilerFetcher::new(
+                    vec![url.into()],
+                    None,
+                    temp_dir(),
+                    ListTimeouts::default(),
+                )
+                .await;
+
+                Compilers::new(fetcher)
             })
             .await
     }
@@ -193,4 +325,82 @@ mod tests {
             _ => panic!("Invalid compilation error: {:?}", result),
         }
     }
+
+    #[tokio::test]
+    async fn cancels_running_compilations() {
+        use async_trait::async_trait;
+        use std::{os::unix::fs::PermissionsExt, time::Duration};
+
+        let folder = temp_dir().join("blockscout/verification/compilers/cancel/");
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        // fake compiler reporting its pid and never finishing
+        let pid_file = folder.join("pid");
+        let solc = folder.join("solc");
+        std::fs::write(
+            &solc,
+            format!(
+                "#!/bin/sh\necho $$ > {}\nexec sleep 60\n",
+                pid_file.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&solc, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        struct LocalSolc(PathBuf);
+        #[async_trait]
+        impl Fetcher for LocalSolc {
+            type Error = std::convert::Infallible;
+            async fn fetch(&self, _ver: &CompilerVersion) -> Result<PathBuf, Self::Error> {
+                Ok(self.0.clone())
+            }
+        }
+
+        let compilers = Arc::new(Compilers::new(LocalSolc(solc)));
+        let version = CompilerVersion::from_str("v0.8.10+commit.fc410830").unwrap();
+        let input: CompilerInput = Input::with_source_code("".into()).into();
+        let compilation = {
+            let compilers = compilers.clone();
+            let version = version.clone();
+            let input = input.clone();
+            tokio::spawn(async move { compilers.compile(&version, &input).await })
+        };
+
+        let mut pid = None;
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            pid = std::fs::read_to_string(&pid_file)
+                .ok()
+                .filter(|pid| pid.ends_with('\n'));
+            if pid.is_some() {
+                break;
+            }
+        }
+        let pid = pid.expect("compiler should be started");
+        compilers.cancel_compilations();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), compilation)
+            .await
+            .expect("compilation should be cancelled")
+            .unwrap();
+        assert!(
+            matches!(result, Err(CompilersError::Cancelled)),
+            "unexpected result: {:?}",
+            result.map(|_| ())
+        );
+        // the killed process is either reaped already or is a zombie
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let state = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()))
+            .ok()
+            .and_then(|stat| stat.rsplit(')').next().map(|rest| rest.trim().to_string()));
+        assert!(
+            state.as_ref().is_none_or(|state| state.starts_with('Z')),
+            "compiler should be killed, process state: {:?}",
+            state
+        );
+
+        // further compilations are rejected
+        let result = compilers.compile(&version, &input).await;
+        assert!(matches!(result, Err(CompilersError::Cancelled)));
+    }
 }
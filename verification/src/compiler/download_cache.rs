@@ -1,4 +1,5 @@
 use super::{fetcher::Fetcher, version::CompilerVersion};
+use crate::metrics;
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 #[derive(Default)]
@@ -26,6 +27,40 @@ impl DownloadCache {
             None => None,
         }
     }
+
+    /// Checks whether the compiler has already been downloaded.
+    /// Compilers that are being downloaded at the moment are not considered as downloaded.
+    pub fn is_downloaded(&self, ver: &CompilerVersion) -> bool {
+        let entry = {
+            let cache = self.cache.lock();
+            cache.get(ver).cloned()
+        };
+        entry
+            .and_then(|lock| lock.try_read().ok().map(|file| file.is_some()))
+            .unwrap_or(false)
+    }
+
+    /// Returns already downloaded compilers with paths to them.
+    pub fn downloaded(&self) -> Vec<(CompilerVersion, PathBuf)> {
+        let cache = self.cache.lock();
+        cache
+            .iter()
+            .filter_map(|(ver, lock)| {
+                let file = lock.try_read().ok()?.clone()?;
+                Some((ver.clone(), file))
+            })
+            .collect()
+    }
+
+    /// Removes the compiler from the cache, waiting for its download to finish if in progress.
+    /// Returns the path to the compiler if it has been downloaded.
+    pub async fn evict(&self, ver: &CompilerVersion) -> Option<PathBuf> {
+        let entry = self.cache.lock().remove(ver);
+        match entry {
+            Some(lock) => lock.write().await.take(),
+            None => None,
+        }
+    }
 }
 
 impl DownloadCache {
@@ -35,8 +70,14 @@ impl DownloadCache {
         ver: &CompilerVersion,
     ) -> Result<PathBuf, D::Error> {
         match self.try_get(ver).await {
-            Some(file) => Ok(file),
-            None => self.fetch(fetcher, ver).await,
+            Some(file) => {
+                metrics::DOWNLOAD_CACHE.with_label_values(&["hit"]).inc();
+                Ok(file)
+            }
+            None => {
+                metrics::DOWNLOAD_CACHE.with_label_values(&["miss"]).inc();
+                self.fetch(fetcher, ver).await
+            }
         }
     }
 
@@ -106,7 +147,9 @@ mod tests {
             assert_eq!(value, PathBuf::from(ver.to_string()));
         };
 
+        assert!(!cache.is_downloaded(&vers[0]));
         get_and_check(&vers[0]);
+        assert!(cache.is_downloaded(&vers[0]));
         get_and_check(&vers[1]);
         get_and_check(&vers[0]);
         get_and_check(&vers[0]);
@@ -122,6 +165,44 @@ mod tests {
         assert!(counter.values().all(|&count| count == 1));
     }
 
+    #[tokio::test]
+    async fn evict_downloaded() {
+        struct MockFetcher;
+
+        #[async_trait]
+        impl Fetcher for MockFetcher {
+            type Error = ();
+            async fn fetch(&self, ver: &CompilerVersion) -> Result<PathBuf, Self::Error> {
+                Ok(PathBuf::from(ver.to_string()))
+            }
+        }
+
+        let cache = DownloadCache::new();
+        let vers: Vec<_> = (0..2).map(new_version).collect();
+        for ver in &vers {
+            cache.get(&MockFetcher, ver).await.unwrap();
+        }
+        let mut downloaded = cache.downloaded();
+        downloaded.sort();
+        assert_eq!(
+            downloaded,
+            vers.iter()
+                .map(|ver| (ver.clone(), PathBuf::from(ver.to_string())))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            cache.evict(&vers[0]).await,
+            Some(PathBuf::from(vers[0].to_string()))
+        );
+        assert_eq!(cache.evict(&vers[0]).await, None);
+        assert!(!cache.is_downloaded(&vers[0]));
+        assert_eq!(
+            cache.downloaded(),
+            vec![(vers[1].clone(), PathBuf::from(vers[1].to_string()))]
+        );
+    }
+
     /// Tests, that cache will not block requests for already downloaded values,
     /// while it downloads others
     #[tokio::test]
@@ -137,7 +218,7 @@ mod tests {
         impl Fetcher for MockBlockingFetcher {
             type Error = ();
             async fn fetch(&self, ver: &CompilerVersion) -> Result<PathBuf, Self::Error> {
-                self.sync.lock().await;
+                let _guard = self.sync.lock().await;
                 Ok(PathBuf::from(ver.to_string()))
             }
         }
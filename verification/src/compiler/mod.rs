@@ -1,9 +1,11 @@
+mod compilation_cache;
 mod compilers;
 mod download_cache;
 mod fetcher;
 mod version;
 
+pub use compilation_cache::CompilationCache;
 pub use compilers::{Compilers, CompilersError};
 pub use download_cache::DownloadCache;
 pub use fetcher::{Fetcher, VersionList};
-pub use version::CompilerVersion;
+pub use version::{CompilerVersion, ReleaseVersion, ResolveError};
@@ -13,6 +13,19 @@ pub enum ParseError {
     CommitHash(hex::FromHexError),
 }
 
+#[derive(Error, Debug)]
+pub enum ResolveError {
+    #[error("invalid compiler version {0}")]
+    Invalid(String),
+    #[error("compiler version {0} not found")]
+    NotFound(String),
+    #[error("compiler version {requested} is ambiguous, candidates: {}", candidates.join(", "))]
+    Ambiguous {
+        requested: String,
+        candidates: Vec<String>,
+    },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ReleaseVersion {
     pub version: Version,
@@ -149,6 +162,71 @@ impl FromStr for CompilerVersion {
     }
 }
 
+impl CompilerVersion {
+    /// Resolves the version requested by a user against the list of available versions.
+    /// Besides the full form accepted by [`FromStr`], supports:
+    /// `latest` - the latest release build,
+    /// `nightly`, `latest-nightly` - the latest nightly build,
+    /// `(v)*VERSION*` - the release build of the version, e.g. `0.8.10` or `v0.8.10`,
+    /// `(v)*VERSION*-nightly.*DATE*` - the nightly build of the version from that date.
+    ///
+    /// Full versions are returned as is, even if they are absent in the list.
+    pub fn resolve(requested: &str, available: &[CompilerVersion]) -> Result<Self, ResolveError> {
+        let requested = requested.trim();
+        if let Ok(version) = CompilerVersion::from_str(requested) {
+            return Ok(version);
+        }
+        let candidates: Vec<&CompilerVersion> = match requested {
+            "latest" => available
+                .iter()
+                .filter(|v| v.is_release())
+                .max()
+                .into_iter()
+                .collect(),
+            "nightly" | "latest-nightly" => available
+                .iter()
+                .filter(|v| !v.is_release())
+                .max()
+                .into_iter()
+                .collect(),
+            short => {
+                let invalid = || ResolveError::Invalid(requested.to_string());
+                let version = Version::parse(short.strip_prefix('v').unwrap_or(short))
+                    .map_err(|_| invalid())?;
+                if !version.build.is_empty() {
+                    return Err(invalid());
+                }
+                let core = Version::new(version.major, version.minor, version.patch);
+                if version.pre.is_empty() {
+                    available
+                        .iter()
+                        .filter(|v| v.is_release() && v.version() == &core)
+                        .collect()
+                } else {
+                    let date = version
+                        .pre
+                        .as_str()
+                        .strip_prefix("nightly.")
+                        .and_then(|date| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+                        .ok_or_else(invalid)?;
+                    available
+                        .iter()
+                        .filter(|v| v.version() == &core && v.date() == Some(&date))
+                        .collect()
+                }
+            }
+        };
+        match candidates.as_slice() {
+            [] => Err(ResolveError::NotFound(requested.to_string())),
+            [version] => Ok((*version).clone()),
+            _ => Err(ResolveError::Ambiguous {
+                requested: requested.to_string(),
+                candidates: candidates.iter().map(|v| v.to_string()).collect(),
+            }),
+        }
+    }
+}
+
 impl Display for CompilerVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -190,8 +268,7 @@ mod tests {
     where
         <T as std::str::FromStr>::Err: std::fmt::Debug,
     {
-        let ver = T::from_str(ver_str).unwrap();
-        ver
+        T::from_str(ver_str).unwrap()
     }
 
     #[test]
@@ -442,4 +519,79 @@ mod tests {
             100,
         );
     }
+
+    #[test]
+    fn resolve_versions() {
+        let available: Vec<CompilerVersion> = [
+            "v0.8.15-nightly.2022.5.27+commit.095cc647",
+            "v0.8.15-nightly.2022.5.26+commit.7c4ad5fa",
+            "v0.8.14+commit.80d49f37",
+            "v0.8.14-nightly.2022.5.17+commit.80d49f37",
+            "v0.4.13+commit.0fb4cb1a",
+        ]
+        .iter()
+        .map(|v| CompilerVersion::from_str(v).unwrap())
+        .collect();
+        let resolve = |requested| {
+            CompilerVersion::resolve(requested, &available)
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(resolve("0.8.14"), "v0.8.14+commit.80d49f37");
+        assert_eq!(resolve("v0.8.14"), "v0.8.14+commit.80d49f37");
+        assert_eq!(resolve(" 0.4.13 "), "v0.4.13+commit.0fb4cb1a");
+        assert_eq!(resolve("latest"), "v0.8.14+commit.80d49f37");
+        assert_eq!(
+            resolve("latest-nightly"),
+            "v0.8.15-nightly.2022.5.27+commit.095cc647"
+        );
+        assert_eq!(resolve("nightly"), resolve("latest-nightly"));
+        assert_eq!(
+            resolve("0.8.15-nightly.2022.5.26"),
+            "v0.8.15-nightly.2022.5.26+commit.7c4ad5fa"
+        );
+        // full versions are not checked against the list
+        assert_eq!(resolve("0.8.16+commit.07a7930e"), "v0.8.16+commit.07a7930e");
+    }
+
+    #[test]
+    fn resolve_invalid_versions() {
+        let available: Vec<CompilerVersion> = [
+            "v0.8.15-nightly.2022.5.27+commit.095cc647",
+            "v0.8.14+commit.80d49f37",
+            "v0.8.14+commit.deadbeef",
+        ]
+        .iter()
+        .map(|v| CompilerVersion::from_str(v).unwrap())
+        .collect();
+        let resolve = |requested| CompilerVersion::resolve(requested, &available).unwrap_err();
+
+        for invalid in ["", "0.8", "sometext", "0.8.14-pre", "0.8.14+commit"] {
+            assert!(
+                matches!(resolve(invalid), ResolveError::Invalid(_)),
+                "version: {:?}",
+                invalid
+            );
+        }
+        // nightly builds are not used instead of missing releases
+        for missing in ["0.8.15", "0.8.13", "0.8.15-nightly.2022.5.26"] {
+            assert!(
+                matches!(resolve(missing), ResolveError::NotFound(_)),
+                "version: {:?}",
+                missing
+            );
+        }
+        match resolve("0.8.14") {
+            ResolveError::Ambiguous { candidates, .. } => assert_eq!(
+                candidates,
+                ["v0.8.14+commit.80d49f37", "v0.8.14+commit.deadbeef"]
+            ),
+            err => panic!("expected ambiguity error, got {:?}", err),
+        }
+        assert!(matches!(
+            CompilerVersion::resolve("latest", &[]),
+            Err(ResolveError::NotFound(_))
+        ));
+    }
 }
@@ -1,8 +1,8 @@
-use crate::consts::DEFAULT_COMPILER_LIST;
+use crate::{consts::DEFAULT_COMPILER_LIST, solidity::ListSource};
 use config::{Config as LibConfig, File};
 use cron::Schedule;
 use serde::Deserialize;
-use std::{net::SocketAddr, num::NonZeroUsize, path::PathBuf, str::FromStr};
+use std::{collections::BTreeMap, net::SocketAddr, num::NonZeroUsize, path::PathBuf, str::FromStr};
 use url::Url;
 
 #[derive(Deserialize, Clone, Default)]
@@ -11,18 +11,25 @@ pub struct Config {
     pub server: ServerConfiguration,
     pub solidity: SolidityConfiguration,
     pub sourcify: SourcifyConfiguration,
+    pub admin: AdminConfiguration,
 }
 
 #[derive(Deserialize, Clone)]
 #[serde(default)]
 pub struct ServerConfiguration {
     pub addr: SocketAddr,
+    /// If specified, gRPC server is started at the address.
+    pub grpc_addr: Option<SocketAddr>,
+    /// Time (in seconds) running requests are given to finish on shutdown.
+    pub shutdown_timeout: u64,
 }
 
 impl Default for ServerConfiguration {
     fn default() -> Self {
         Self {
             addr: SocketAddr::from_str("0.0.0.0:8043").expect("should be valid url"),
+            grpc_addr: None,
+            shutdown_timeout: 30,
         }
     }
 }
@@ -31,17 +38,126 @@ impl Default for ServerConfiguration {
 #[serde(default)]
 pub struct SolidityConfiguration {
     pub enabled: bool,
-    pub compilers_list_url: Url,
+    /// Backend the compilers are obtained with.
+    pub fetcher: FetcherKind,
+    /// Folder compilers are downloaded into by `list` backend
+    /// and taken from by `local` backend.
+    pub compilers_folder: PathBuf,
+    /// Lists of available compilers, merged into one.
+    pub compilers_lists: Vec<CompilersListConfiguration>,
+    /// Deprecated, use `compilers_lists` instead.
+    /// If specified, `compilers_lists` consists of the single list.
+    pub compilers_list_url: Option<Url>,
+    /// Time (in seconds) to connect to the host of a compilers list.
+    pub compilers_list_connect_timeout: u64,
+    /// Total time (in seconds) the request of a compilers list may take.
+    pub compilers_list_timeout: u64,
     #[serde(with = "serde_with::rust::display_fromstr")]
     pub refresh_versions_schedule: Schedule,
+    pub compilation_cache: CompilationCacheConfiguration,
+    /// Versions downloaded at startup, in any form accepted by verification requests.
+    pub prefetch_versions: Vec<String>,
 }
 
 impl Default for SolidityConfiguration {
     fn default() -> Self {
         Self {
-            compilers_list_url: Url::try_from(DEFAULT_COMPILER_LIST).expect("valid url"),
+            compilers_lists: vec![CompilersListConfiguration::new(
+                Url::try_from(DEFAULT_COMPILER_LIST).expect("valid url"),
+            )],
+            compilers_list_url: None,
+            compilers_list_connect_timeout: 10,
+            compilers_list_timeout: 60,
             enabled: true,
+            fetcher: FetcherKind::List,
+            compilers_folder: "compilers/".into(),
             refresh_versions_schedule: Schedule::from_str("0 0 * * * * *").unwrap(), // every hour
+            compilation_cache: Default::default(),
+            prefetch_versions: vec![],
+        }
+    }
+}
+
+impl SolidityConfiguration {
+    /// Turns deprecated `compilers_list_url` into the single entry of `compilers_lists`.
+    fn migrate_compilers_list_url(&mut self) -> Result<(), config::ConfigError> {
+        if let Some(url) = self.compilers_list_url.take() {
+            if self.compilers_lists != Self::default().compilers_lists {
+                return Err(config::ConfigError::Message(
+                    "deprecated `solidity.compilers_list_url` can't be used together with \
+                     `solidity.compilers_lists`, specify the list in `solidity.compilers_lists` only"
+                        .into(),
+                ));
+            }
+            log::warn!("`solidity.compilers_list_url` is deprecated, use `solidity.compilers_lists` instead");
+            self.compilers_lists = vec![CompilersListConfiguration::new(url)];
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FetcherKind {
+    /// Compilers are downloaded from `compilers_lists`.
+    List,
+    /// Compilers are installed with svm into its home folder (`~/.svm`).
+    Svm,
+    /// Compilers already present in `compilers_folder` are used.
+    Local,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct CompilersListConfiguration {
+    pub url: Url,
+    /// If several lists contain the same version, the one with higher priority is used.
+    /// Lists with equal priorities are used in the order they are specified.
+    #[serde(default)]
+    pub priority: i32,
+    /// Base urls of mirrors serving the same files as the list.
+    #[serde(default)]
+    pub mirrors: Vec<Url>,
+}
+
+impl CompilersListConfiguration {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            priority: 0,
+            mirrors: vec![],
+        }
+    }
+}
+
+impl From<CompilersListConfiguration> for ListSource {
+    fn from(config: CompilersListConfiguration) -> Self {
+        Self {
+            url: config.url,
+            priority: config.priority,
+            mirrors: config.mirrors,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct CompilationCacheConfiguration {
+    pub enabled: bool,
+    /// Maximum total size (in megabytes) of serialized compilation outputs kept in memory.
+    pub max_size_mb: NonZeroUsize,
+    /// If specified, compilation outputs are also persisted into the folder.
+    pub folder: Option<PathBuf>,
+    /// Maximum total size (in megabytes) of compilation outputs persisted into the folder.
+    pub max_folder_size_mb: NonZeroUsize,
+}
+
+impl Default for CompilationCacheConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_size_mb: NonZeroUsize::new(256).expect("Is not zero"),
+            folder: None,
+            max_folder_size_mb: NonZeroUsize::new(1024).expect("Is not zero"),
         }
     }
 }
@@ -55,6 +171,23 @@ pub struct SourcifyConfiguration {
     /// Should be at least one. Set to `3` by default.
     pub verification_attempts: NonZeroUsize,
     pub request_timeout: u64,
+    /// Delay (in milliseconds) before the first retry of a failed request,
+    /// doubled (with random jitter) after each subsequent failure.
+    pub initial_retry_delay_ms: u64,
+    /// Maximum delay (in milliseconds) between two attempts.
+    pub max_retry_delay_ms: u64,
+    /// Time (in seconds) all attempts of a request together may take.
+    pub retry_deadline: u64,
+    /// Chain ids by the names of the networks (`{network}/{subnet}`, as Blockscout
+    /// instances are named), so requests may specify chains by their names.
+    pub chains: BTreeMap<String, u64>,
+    /// Time (in seconds) the list of chains supported by Sourcify is cached for.
+    pub chains_cache_ttl: u64,
+    /// Time (in seconds) verification sessions are kept for since their last use.
+    /// Sessions are kept in memory, so several replicas require sticky routing.
+    pub session_ttl: u64,
+    /// Maximum number of verification sessions kept at once by an instance.
+    pub max_sessions: usize,
 }
 
 impl Default for SourcifyConfiguration {
@@ -64,10 +197,32 @@ impl Default for SourcifyConfiguration {
             api_url: Url::try_from("https://sourcify.dev/server/").expect("valid url"),
             verification_attempts: NonZeroUsize::new(3).expect("Is not zero"),
             request_timeout: 10,
+            initial_retry_delay_ms: 500,
+            max_retry_delay_ms: 5_000,
+            retry_deadline: 30,
+            chains: BTreeMap::from([
+                ("eth/mainnet".into(), 1),
+                ("eth/goerli".into(), 5),
+                ("etc/mainnet".into(), 61),
+                ("poa/sokol".into(), 77),
+                ("poa/core".into(), 99),
+                ("xdai/mainnet".into(), 100),
+            ]),
+            chains_cache_ttl: 60 * 60,
+            session_ttl: 60 * 60,
+            max_sessions: 1000,
         }
     }
 }
 
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AdminConfiguration {
+    /// Admin routes are available only if the token is specified
+    /// and solidity verification is enabled.
+    pub token: Option<String>,
+}
+
 impl Config {
     pub fn from_file(file: PathBuf) -> Result<Self, config::ConfigError> {
         let mut builder =
@@ -75,9 +230,53 @@ impl Config {
         if file.exists() {
             builder = builder.add_source(File::from(file));
         }
-        builder
+        let mut config: Self = builder
             .build()
             .expect("Failed to build config")
-            .try_deserialize()
+            .try_deserialize()?;
+        config.solidity.migrate_compilers_list_url()?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::FileFormat;
+
+    fn parse(toml: &str) -> Result<Config, config::ConfigError> {
+        let mut config: Config = LibConfig::builder()
+            .add_source(File::from_str(toml, FileFormat::Toml))
+            .build()?
+            .try_deserialize()?;
+        config.solidity.migrate_compilers_list_url()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn deprecated_compilers_list_url() {
+        let config = parse(
+            r#"
+            [solidity]
+            compilers_list_url = "https://example.com/list.json"
+            "#,
+        )
+        .expect("deprecated key should be accepted");
+        assert_eq!(
+            config.solidity.compilers_lists,
+            vec![CompilersListConfiguration::new(
+                Url::try_from("https://example.com/list.json").unwrap()
+            )]
+        );
+
+        let parsed = parse(
+            r#"
+            [solidity]
+            compilers_list_url = "https://example.com/list.json"
+            [[solidity.compilers_lists]]
+            url = "https://example.com/other_list.json"
+            "#,
+        );
+        assert!(parsed.is_err(), "both keys should not be accepted");
     }
 }
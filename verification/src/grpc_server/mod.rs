@@ -0,0 +1,220 @@
+mod solidity;
+mod sourcify;
+
+// generated clients and helpers are used by the tests only
+#[allow(dead_code)]
+pub mod proto {
+    tonic::include_proto!("blockscout.verification.v1");
+}
+
+use self::{
+    proto::{
+        solidity_verifier_server::SolidityVerifierServer,
+        sourcify_verifier_server::SourcifyVerifierServer,
+    },
+    solidity::SolidityService,
+    sourcify::SourcifyService,
+};
+use crate::{
+    http_server::error::{ApiError, ErrorCode},
+    AppRouter, MatchType, VerificationResponse, VerificationResult, VerificationStatus,
+};
+use std::{future::Future, net::SocketAddr, sync::Arc};
+use tonic::{metadata::MetadataValue, transport::Server, Code, Status};
+
+/// Metadata key the machine-readable error code is returned under.
+pub const ERROR_CODE_KEY: &str = "x-error-code";
+
+/// Runs gRPC server exposing the same operations as HTTP API does.
+/// Services use the same compilers and Sourcify client as HTTP routers of `app_router`,
+/// and services for disabled subsystems are not registered. Stops accepting requests
+/// once `signal` resolves and returns after the running ones are finished.
+pub async fn run_with_shutdown(
+    socket_addr: SocketAddr,
+    app_router: Arc<AppRouter>,
+    signal: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    log::info!("Verification gRPC server is starting at {}", socket_addr);
+    let solidity = app_router
+        .compilers()
+        .map(|compilers| SolidityVerifierServer::new(SolidityService::new(compilers)));
+    let sourcify = app_router
+        .sourcify_client()
+        .map(|client| SourcifyVerifierServer::new(SourcifyService::new(client)));
+    Server::builder()
+        .add_optional_service(solidity)
+        .add_optional_service(sourcify)
+        .serve_with_shutdown(socket_addr, signal)
+        .await
+}
+
+fn error_code(code: ErrorCode) -> String {
+    serde_json::to_value(code)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .expect("error codes are serialized as strings")
+}
+
+impl From<ApiError> for Status {
+    fn from(err: ApiError) -> Self {
+        let status_code = err.code.status_code();
+        let code = if status_code.is_client_error() {
+            Code::InvalidArgument
+        } else if status_code == actix_web::http::StatusCode::SERVICE_UNAVAILABLE {
+            Code::Unavailable
+        } else {
+            Code::Internal
+        };
+        let mut status = Status::new(code, err.message);
+        let error_code = MetadataValue::try_from(error_code(err.code))
+            .expect("error codes are valid metadata values");
+        status.metadata_mut().insert(ERROR_CODE_KEY, error_code);
+        status
+    }
+}
+
+impl From<VerificationResponse> for proto::VerifyResponse {
+    fn from(response: VerificationResponse) -> Self {
+        let status = match response.status {
+            VerificationStatus::Ok => proto::verify_response::Status::Success,
+            VerificationStatus::Failed => proto::verify_response::Status::Failure,
+        };
+        Self {
+            message: response.message,
+            status: status.into(),
+            result: response.result.map(Into::into),
+            code: response.code.map(error_code),
+        }
+    }
+}
+
+impl From<VerificationResult> for proto::VerificationResult {
+    fn from(result: VerificationResult) -> Self {
+        Self {
+            file_name: result.file_name,
+            contract_name: result.contract_name,
+            compiler_version: result.compiler_version,
+            evm_version: result.evm_version,
+            constructor_arguments: result.constructor_arguments.map(|args| args.to_string()),
+            optimization: result.optimization,
+            optimization_runs: result.optimization_runs.map(|runs| runs as u32),
+            contract_libraries: result.contract_libraries.into_iter().collect(),
+            abi: result.abi,
+            sources: result.sources.into_iter().collect(),
+            match_type: result.match_type.map(|match_type| match match_type {
+                MatchType::Perfect => "perfect".to_string(),
+                MatchType::Partial => "partial".to_string(),
+            }),
+            storage_timestamp: result.storage_timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        proto::{
+            sourcify_verifier_client::SourcifyVerifierClient, verify_response::Status,
+            VerifySourcifyRequest,
+        },
+        *,
+    };
+    use crate::Config;
+    use serde_json::json;
+    use std::{collections::HashMap, time::Duration};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    async fn start_server(sourcify_response: ResponseTemplate) -> (MockServer, SocketAddr) {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/"))
+            .respond_with(sourcify_response)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = Config::default();
+        config.solidity.enabled = false;
+        config.sourcify.api_url = mock_server.uri().parse().unwrap();
+        let app_router = Arc::new(
+            AppRouter::new(config)
+                .await
+                .expect("couldn't initialize the app"),
+        );
+
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("couldn't find a free port");
+        tokio::spawn(run_with_shutdown(addr, app_router, std::future::pending()));
+        (mock_server, addr)
+    }
+
+    async fn connect(addr: SocketAddr) -> SourcifyVerifierClient<tonic::transport::Channel> {
+        let url = format!("http://{}", addr);
+        for _ in 0..50 {
+            if let Ok(client) = SourcifyVerifierClient::connect(url.clone()).await {
+                return client;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("couldn't connect to gRPC server at {}", addr);
+    }
+
+    fn request() -> VerifySourcifyRequest {
+        VerifySourcifyRequest {
+            address: "0x1234567890123456789012345678901234567890".to_string(),
+            chain: "77".to_string(),
+            files: HashMap::new(),
+            chosen_contract: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn sourcify_verification_failure() {
+        let (_mock_server, addr) = start_server(
+            ResponseTemplate::new(400).set_body_json(json!({ "error": "Metadata file not found" })),
+        )
+        .await;
+        let mut client = connect(addr).await;
+
+        let response = client
+            .verify(request())
+            .await
+            .expect("request should succeed")
+            .into_inner();
+
+        assert_eq!(response.status(), Status::Failure);
+        assert_eq!(response.message, "Metadata file not found");
+        assert_eq!(
+            response.code.as_deref(),
+            Some("SOURCIFY_VERIFICATION_FAILED")
+        );
+        assert!(response.result.is_none());
+    }
+
+    #[tokio::test]
+    async fn sourcify_validation_errors() {
+        let (_mock_server, addr) = start_server(ResponseTemplate::new(400).set_body_json(json!({
+            "message": "Validation Error",
+            "errors": [{ "field": "chain", "message": "Invalid chain" }],
+        })))
+        .await;
+        let mut client = connect(addr).await;
+
+        let status = client
+            .verify(request())
+            .await
+            .expect_err("request should fail");
+
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            status
+                .metadata()
+                .get(ERROR_CODE_KEY)
+                .and_then(|code| code.to_str().ok()),
+            Some("SOURCIFY_VALIDATION_FAILED")
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use super::proto::{
+    self, list_versions_request, solidity_verifier_server::SolidityVerifier, ListVersionsRequest,
+    ListVersionsResponse, VerifyMultiPartRequest, VerifyResponse, VerifyStandardJsonRequest,
+};
+use crate::{
+    compiler::Compilers,
+    http_server::{
+        error::{ApiError, ErrorCode},
+        handlers::verification::{
+            count_verification_request,
+            solidity::{
+                multi_part::verify_multi_part,
+                standard_json::verify_standard_json,
+                types::{
+                    MultiPartFiles, StandardJson, VerificationRequest, VersionKind,
+                    VersionMetadata, VersionsQuery,
+                },
+                version_list::list_versions,
+            },
+        },
+    },
+    solidity::AnyFetcher,
+};
+use actix_web::web;
+use tonic::{Request, Response, Status};
+
+pub struct SolidityService {
+    compilers: web::Data<Compilers<AnyFetcher>>,
+}
+
+impl SolidityService {
+    pub fn new(compilers: web::Data<Compilers<AnyFetcher>>) -> Self {
+        Self { compilers }
+    }
+}
+
+impl From<VerifyMultiPartRequest> for VerificationRequest<MultiPartFiles> {
+    fn from(request: VerifyMultiPartRequest) -> Self {
+        // proto maps cannot be optional, so empty libraries are treated as missing ones
+        let contract_libraries = (!request.contract_libraries.is_empty())
+            .then(|| request.contract_libraries.into_iter().collect());
+        Self {
+            deployed_bytecode: request.deployed_bytecode,
+            creation_bytecode: request.creation_bytecode,
+            compiler_version: request.compiler_version,
+            content: MultiPartFiles {
+                sources: request
+                    .sources
+                    .into_iter()
+                    .map(|(path, content)| (path.into(), content))
+                    .collect(),
+                evm_version: request.evm_version,
+                optimization_runs: request.optimization_runs.map(|runs| runs as usize),
+                contract_libraries,
+            },
+        }
+    }
+}
+
+impl TryFrom<VerifyStandardJsonRequest> for VerificationRequest<StandardJson> {
+    type Error = ApiError;
+
+    fn try_from(request: VerifyStandardJsonRequest) -> Result<Self, Self::Error> {
+        let input = serde_json::from_str(&request.input)
+            .map_err(|err| ApiError::new(ErrorCode::InvalidRequest, err))?;
+        Ok(Self {
+            deployed_bytecode: request.deployed_bytecode,
+            creation_bytecode: request.creation_bytecode,
+            compiler_version: request.compiler_version,
+            content: StandardJson { input },
+        })
+    }
+}
+
+impl From<ListVersionsRequest> for VersionsQuery {
+    fn from(request: ListVersionsRequest) -> Self {
+        let kind = match request.kind() {
+            list_versions_request::VersionKind::Any => None,
+            list_versions_request::VersionKind::Release => Some(VersionKind::Release),
+            list_versions_request::VersionKind::Nightly => Some(VersionKind::Nightly),
+        };
+        Self {
+            kind,
+            version: request.version,
+            metadata: request.metadata,
+        }
+    }
+}
+
+impl From<VersionMetadata> for proto::VersionMetadata {
+    fn from(metadata: VersionMetadata) -> Self {
+        Self {
+            version: metadata.version,
+            release: metadata.kind == VersionKind::Release,
+            date: metadata.date,
+            download_url: metadata.download_url.map(|url| url.to_string()),
+            sha256: metadata.sha256.map(|sha256| format!("{:x}", sha256)),
+            downloaded: metadata.downloaded,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl SolidityVerifier for SolidityService {
+    async fn verify_multi_part(
+        &self,
+        request: Request<VerifyMultiPartRequest>,
+    ) -> Result<Response<VerifyResponse>, Status> {
+        let response = verify_multi_part(&self.compilers, request.into_inner().into()).await;
+        count_verification_request("multiple-files", &response);
+        Ok(Response::new(response?.into()))
+    }
+
+    async fn verify_standard_json(
+        &self,
+        request: Request<VerifyStandardJsonRequest>,
+    ) -> Result<Response<VerifyResponse>, Status> {
+        let params = request.into_inner().try_into()?;
+        let response = verify_standard_json(&self.compilers, params).await;
+        count_verification_request("standard-json", &response);
+        Ok(Response::new(response?.into()))
+    }
+
+    async fn list_versions(
+        &self,
+        request: Request<ListVersionsRequest>,
+    ) -> Result<Response<ListVersionsResponse>, Status> {
+        let response = list_versions(&self.compilers, &request.into_inner().into()).await?;
+        Ok(Response::new(ListVersionsResponse {
+            versions: response.versions,
+            metadata: response
+                .metadata
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }))
+    }
+}
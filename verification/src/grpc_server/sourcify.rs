@@ -0,0 +1,49 @@
+use super::proto::{
+    sourcify_verifier_server::SourcifyVerifier, VerifyResponse, VerifySourcifyRequest,
+};
+use crate::http_server::handlers::{
+    sourcify::{
+        types::{ApiRequest, Files},
+        verify_using_client, SourcifyApiClient,
+    },
+    verification::count_verification_request,
+};
+use actix_web::web;
+use tonic::{Request, Response, Status};
+
+pub struct SourcifyService {
+    client: web::Data<SourcifyApiClient>,
+}
+
+impl SourcifyService {
+    pub fn new(client: web::Data<SourcifyApiClient>) -> Self {
+        Self { client }
+    }
+}
+
+impl From<VerifySourcifyRequest> for ApiRequest {
+    fn from(request: VerifySourcifyRequest) -> Self {
+        Self {
+            address: request.address,
+            chain: request.chain,
+            files: Files(request.files.into_iter().collect()),
+            chosen_contract: request.chosen_contract.map(|index| index as usize),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl SourcifyVerifier for SourcifyService {
+    async fn verify(
+        &self,
+        request: Request<VerifySourcifyRequest>,
+    ) -> Result<Response<VerifyResponse>, Status> {
+        let response = verify_using_client(
+            self.client.clone().into_inner(),
+            request.into_inner().into(),
+        )
+        .await;
+        count_verification_request("sourcify", &response);
+        Ok(Response::new(response?.into()))
+    }
+}
@@ -0,0 +1,323 @@
+//! Typed errors returned by the API.
+//!
+//! Every error is rendered as a JSON object with a stable machine-readable
+//! `code` and a human-readable `message`. Clients should rely on the codes,
+//! while messages are subject to change.
+
+use crate::{
+    compiler::{CompilersError, ResolveError},
+    solidity::{
+        local_fetcher, svm_fetcher, AnyFetchError, FetchError, InitializationError,
+        VerificationError,
+    },
+};
+use actix_web::{
+    error::{JsonPayloadError, QueryPayloadError},
+    http::StatusCode,
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    // Request errors
+    InvalidRequest,
+    InvalidCompilerVersion,
+    AmbiguousCompilerVersion,
+    InvalidCompilerInput,
+    InvalidCreationTxInput,
+    InvalidDeployedBytecode,
+    MetadataHashParse,
+    MetadataHashMismatch,
+    InvalidMetadata,
+    Unauthorized,
+
+    // Compiler errors
+    CompilerNotFound,
+    CompilerFetchFailed,
+    CompilersListUnavailable,
+    CompilerInternal,
+    CompilationFailed,
+
+    // Verification failures
+    NoMatchingContracts,
+    AbstractContract,
+    CompilerVersionMismatch,
+    BytecodeMismatch,
+    ExtraDataMismatch,
+    InvalidConstructorArguments,
+    MissedLibrary,
+    VerificationInternal,
+
+    // Sourcify errors
+    SourcifyUnavailable,
+    SourcifyInvalidResponse,
+    SourcifyValidationFailed,
+    SourcifyVerificationFailed,
+    SourcifyContractNotVerified,
+    UnsupportedChain,
+    SessionNotFound,
+    TooManySessions,
+
+    ShuttingDown,
+    Internal,
+}
+
+impl ErrorCode {
+    /// Status code of the response the error is returned with.
+    ///
+    /// Verification failures are not treated as errors of the request
+    /// and are returned inside successful [`VerificationResponse`](crate::VerificationResponse).
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest
+            | ErrorCode::InvalidCompilerVersion
+            | ErrorCode::AmbiguousCompilerVersion
+            | ErrorCode::InvalidCompilerInput
+            | ErrorCode::InvalidCreationTxInput
+            | ErrorCode::InvalidDeployedBytecode
+            | ErrorCode::MetadataHashParse
+            | ErrorCode::MetadataHashMismatch
+            | ErrorCode::InvalidMetadata
+            | ErrorCode::CompilerNotFound
+            | ErrorCode::SourcifyValidationFailed
+            | ErrorCode::UnsupportedChain => StatusCode::BAD_REQUEST,
+
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+
+            ErrorCode::SessionNotFound => StatusCode::NOT_FOUND,
+
+            ErrorCode::CompilationFailed
+            | ErrorCode::NoMatchingContracts
+            | ErrorCode::AbstractContract
+            | ErrorCode::CompilerVersionMismatch
+            | ErrorCode::BytecodeMismatch
+            | ErrorCode::ExtraDataMismatch
+            | ErrorCode::InvalidConstructorArguments
+            | ErrorCode::MissedLibrary
+            | ErrorCode::VerificationInternal
+            | ErrorCode::SourcifyVerificationFailed
+            | ErrorCode::SourcifyContractNotVerified => StatusCode::OK,
+
+            ErrorCode::CompilerFetchFailed
+            | ErrorCode::CompilersListUnavailable
+            | ErrorCode::CompilerInternal
+            | ErrorCode::SourcifyUnavailable
+            | ErrorCode::SourcifyInvalidResponse
+            | ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+
+            ErrorCode::TooManySessions | ErrorCode::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, thiserror::Error)]
+#[error("{message}")]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.code.status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self)
+    }
+}
+
+/// Used as an error handler of the `Json` extractor,
+/// so that malformed requests are reported the same way as other errors.
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::new(ErrorCode::InvalidRequest, err).into()
+}
+
+/// Used as an error handler of the `Query` extractor.
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::new(ErrorCode::InvalidRequest, err).into()
+}
+
+impl From<&InitializationError> for ErrorCode {
+    fn from(err: &InitializationError) -> Self {
+        match err {
+            InitializationError::InvalidCreationTxInput(_) => ErrorCode::InvalidCreationTxInput,
+            InitializationError::InvalidDeployedBytecode(_) => ErrorCode::InvalidDeployedBytecode,
+            InitializationError::MetadataHashParse(_) => ErrorCode::MetadataHashParse,
+            InitializationError::MetadataHashMismatch(_) => ErrorCode::MetadataHashMismatch,
+        }
+    }
+}
+
+impl From<&VerificationError> for ErrorCode {
+    fn from(err: &VerificationError) -> Self {
+        match err {
+            VerificationError::NoContracts => ErrorCode::NoMatchingContracts,
+            VerificationError::InvalidDeployedBytecode(_) => ErrorCode::AbstractContract,
+            VerificationError::CompilerVersionMismatch(_) => ErrorCode::CompilerVersionMismatch,
+            VerificationError::BytecodeMismatch(_) => ErrorCode::BytecodeMismatch,
+            VerificationError::ExtraDataMismatch(_) => ErrorCode::ExtraDataMismatch,
+            VerificationError::InvalidConstructorArguments(_) => {
+                ErrorCode::InvalidConstructorArguments
+            }
+            VerificationError::MissedLibrary => ErrorCode::MissedLibrary,
+            VerificationError::InternalError(_) => ErrorCode::VerificationInternal,
+        }
+    }
+}
+
+impl From<&FetchError> for ErrorCode {
+    fn from(err: &FetchError) -> Self {
+        match err {
+            FetchError::NotFound(_) => ErrorCode::CompilerNotFound,
+            FetchError::Fetch(_)
+            | FetchError::File(_)
+            | FetchError::Shedule(_)
+            | FetchError::HashMismatch { .. } => ErrorCode::CompilerFetchFailed,
+        }
+    }
+}
+
+impl From<&svm_fetcher::FetchError> for ErrorCode {
+    fn from(err: &svm_fetcher::FetchError) -> Self {
+        match err {
+            svm_fetcher::FetchError::NightlyNotSupported | svm_fetcher::FetchError::NotFound(_) => {
+                ErrorCode::CompilerNotFound
+            }
+            svm_fetcher::FetchError::Svm(_) => ErrorCode::CompilerFetchFailed,
+        }
+    }
+}
+
+impl From<&local_fetcher::FetchError> for ErrorCode {
+    fn from(err: &local_fetcher::FetchError) -> Self {
+        match err {
+            local_fetcher::FetchError::NotFound(_) => ErrorCode::CompilerNotFound,
+        }
+    }
+}
+
+impl From<&AnyFetchError> for ErrorCode {
+    fn from(err: &AnyFetchError) -> Self {
+        match err {
+            AnyFetchError::List(err) => err.into(),
+            AnyFetchError::Svm(err) => err.into(),
+            AnyFetchError::Local(err) => err.into(),
+        }
+    }
+}
+
+impl From<&CompilersError> for ErrorCode {
+    fn from(err: &CompilersError) -> Self {
+        match err {
+            CompilersError::Fetch(err) => {
+                // fetch errors are type-erased by `Compilers`,
+                // so we have to recover the original error to get the code
+                if let Some(err) = err.downcast_ref::<AnyFetchError>() {
+                    err.into()
+                } else if let Some(err) = err.downcast_ref::<FetchError>() {
+                    err.into()
+                } else if let Some(err) = err.downcast_ref::<svm_fetcher::FetchError>() {
+                    err.into()
+                } else {
+                    ErrorCode::CompilerFetchFailed
+                }
+            }
+            CompilersError::Internal(_) => ErrorCode::CompilerInternal,
+            CompilersError::Compilation(_) => ErrorCode::CompilationFailed,
+            CompilersError::Cancelled => ErrorCode::ShuttingDown,
+        }
+    }
+}
+
+impl From<&ResolveError> for ErrorCode {
+    fn from(err: &ResolveError) -> Self {
+        match err {
+            ResolveError::Invalid(_) => ErrorCode::InvalidCompilerVersion,
+            ResolveError::NotFound(_) => ErrorCode::CompilerNotFound,
+            ResolveError::Ambiguous { .. } => ErrorCode::AmbiguousCompilerVersion,
+        }
+    }
+}
+
+impl From<ResolveError> for ApiError {
+    fn from(err: ResolveError) -> Self {
+        ApiError::new((&err).into(), err)
+    }
+}
+
+impl From<InitializationError> for ApiError {
+    fn from(err: InitializationError) -> Self {
+        ApiError::new((&err).into(), err)
+    }
+}
+
+impl From<CompilersError> for ApiError {
+    fn from(err: CompilersError) -> Self {
+        ApiError::new((&err).into(), err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::CompilerVersion;
+    use actix_web::body::MessageBody;
+    use anyhow::anyhow;
+    use std::str::FromStr;
+
+    #[test]
+    fn error_response_is_json() {
+        let err = ApiError::new(ErrorCode::InvalidCompilerVersion, "invalid version");
+        let response = err.error_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response.into_body().try_into_bytes().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "code": "INVALID_COMPILER_VERSION",
+                "message": "invalid version",
+            })
+        );
+    }
+
+    #[test]
+    fn fetch_errors_are_recovered() {
+        let version = CompilerVersion::from_str("v0.8.9+commit.e5eed63a").unwrap();
+        let not_found = CompilersError::Fetch(anyhow!(FetchError::NotFound(version.clone())));
+        assert_eq!(ErrorCode::from(&not_found), ErrorCode::CompilerNotFound);
+        assert_eq!(
+            ApiError::from(not_found).status_code(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let nightly = CompilersError::Fetch(anyhow!(svm_fetcher::FetchError::NightlyNotSupported));
+        assert_eq!(ErrorCode::from(&nightly), ErrorCode::CompilerNotFound);
+
+        let local = CompilersError::Fetch(anyhow!(AnyFetchError::Local(
+            local_fetcher::FetchError::NotFound(version.clone())
+        )));
+        assert_eq!(ErrorCode::from(&local), ErrorCode::CompilerNotFound);
+
+        let unknown = CompilersError::Fetch(anyhow!("unknown error"));
+        assert_eq!(ErrorCode::from(&unknown), ErrorCode::CompilerFetchFailed);
+        assert_eq!(
+            ApiError::from(unknown).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Routes managing the compilers downloaded by the service.
+//!
+//! Available only if the admin token is configured, and every request
+//! must be authorized with `Authorization: Bearer <token>` header.
+
+use crate::{
+    compiler::{CompilerVersion, Compilers, VersionList},
+    http_server::error::{ApiError, ErrorCode},
+    solidity::AnyFetcher,
+};
+use actix_web::{
+    dev::Payload,
+    http::header,
+    web::{self, Json},
+    FromRequest, HttpRequest,
+};
+use futures::future::{join_all, ready, Ready};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Token admin requests are authorized with.
+pub struct AdminToken(pub String);
+
+/// Extractor that rejects requests not authorized with the admin token.
+pub struct Authorized;
+
+impl FromRequest for Authorized {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let expected = req
+            .app_data::<web::Data<AdminToken>>()
+            .map(|token| token.0.as_str());
+        let provided = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let result = match (expected, provided) {
+            (Some(expected), Some(provided)) if constant_time_eq(expected, provided) => {
+                Ok(Authorized)
+            }
+            _ => Err(ApiError::new(
+                ErrorCode::Unauthorized,
+                "request is not authorized with admin token",
+            )),
+        };
+        ready(result)
+    }
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DownloadedCompiler {
+    pub version: String,
+    pub path: PathBuf,
+    /// Size of the compiler file in bytes, if it could be obtained
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DownloadedCompilersResponse {
+    pub compilers: Vec<DownloadedCompiler>,
+}
+
+/// Lists compilers that have already been downloaded.
+pub async fn downloaded_compilers(
+    _: Authorized,
+    compilers: web::Data<Compilers<AnyFetcher>>,
+) -> Json<DownloadedCompilersResponse> {
+    let mut downloaded = compilers.downloaded();
+    // sort in descending order
+    downloaded.sort_by(|(x, _), (y, _)| x.cmp(y).reverse());
+    let compilers = join_all(downloaded.into_iter().map(|(version, path)| async move {
+        DownloadedCompiler {
+            version: version.to_string(),
+            size: tokio::fs::metadata(&path).await.map(|meta| meta.len()).ok(),
+            path,
+        }
+    }))
+    .await;
+    Json(DownloadedCompilersResponse { compilers })
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct PrefetchRequest {
+    /// Versions in any form accepted by verification requests
+    pub versions: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PrefetchResponse {
+    /// Requested versions mapped to the downloaded compilers
+    pub downloaded: BTreeMap<String, String>,
+    /// Requested versions mapped to the errors occurred
+    pub failed: BTreeMap<String, ApiError>,
+}
+
+/// Downloads the compilers in advance.
+pub async fn prefetch(
+    _: Authorized,
+    compilers: web::Data<Compilers<AnyFetcher>>,
+    request: Json<PrefetchRequest>,
+) -> Json<PrefetchResponse> {
+    Json(prefetch_versions(&compilers, &request.versions).await)
+}
+
+pub(crate) async fn prefetch_versions(
+    compilers: &Compilers<AnyFetcher>,
+    versions: &[String],
+) -> PrefetchResponse {
+    let available = compilers.all_versions();
+    let results = join_all(versions.iter().map(|requested| async {
+        let version = CompilerVersion::resolve(requested, &available)?;
+        compilers.prefetch(&version).await?;
+        Ok::<_, ApiError>(version)
+    }))
+    .await;
+
+    let mut response = PrefetchResponse::default();
+    for (requested, result) in versions.iter().zip(results) {
+        match result {
+            Ok(version) => {
+                response
+                    .downloaded
+                    .insert(requested.clone(), version.to_string());
+            }
+            Err(err) => {
+                response.failed.insert(requested.clone(), err);
+            }
+        }
+    }
+    response
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct EvictResponse {
+    /// Whether the compiler has been downloaded before the eviction
+    pub evicted: bool,
+}
+
+/// Removes the downloaded compiler, so that it is downloaded again when needed.
+pub async fn evict(
+    _: Authorized,
+    compilers: web::Data<Compilers<AnyFetcher>>,
+    version: web::Path<String>,
+) -> Result<Json<EvictResponse>, ApiError> {
+    if !compilers.fetcher().is_evictable() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "only compilers downloaded from the compilers lists can be evicted",
+        ));
+    }
+    let version = CompilerVersion::resolve(&version, &compilers.all_versions())?;
+    let evicted = compilers
+        .evict(&version)
+        .await
+        .map_err(|err| ApiError::new(ErrorCode::Internal, err))?;
+    Ok(Json(EvictResponse { evicted }))
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct RefreshResponse {
+    /// Number of compiler versions available after the refresh
+    pub versions: usize,
+}
+
+/// Fetches the compilers lists without waiting for the scheduled refresh.
+pub async fn refresh(
+    _: Authorized,
+    compilers: web::Data<Compilers<AnyFetcher>>,
+) -> Result<Json<RefreshResponse>, ApiError> {
+    compilers
+        .fetcher()
+        .refresh_versions()
+        .await
+        .map_err(|err| ApiError::new(ErrorCode::CompilersListUnavailable, format!("{:#}", err)))?;
+    Ok(Json(RefreshResponse {
+        versions: compilers.all_versions().len(),
+    }))
+}
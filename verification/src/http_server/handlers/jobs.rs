@@ -0,0 +1,15 @@
+use crate::scheduler::{JobStatus, Scheduler};
+use actix_web::web::{self, Json};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct JobsResponse {
+    pub jobs: Vec<JobStatus>,
+}
+
+/// Returns the state of background jobs run by the service.
+pub async fn jobs(scheduler: web::Data<Scheduler>) -> Json<JobsResponse> {
+    Json(JobsResponse {
+        jobs: scheduler.jobs(),
+    })
+}
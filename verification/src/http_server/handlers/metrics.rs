@@ -0,0 +1,13 @@
+use actix_web::{error, Error, HttpResponse};
+use prometheus::{Encoder, TextEncoder};
+
+pub async fn metrics() -> Result<HttpResponse, Error> {
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    encoder
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok()
+        .content_type(encoder.format_type())
+        .body(buffer))
+}
@@ -1,7 +1,11 @@
+pub mod admin;
+pub mod jobs;
+pub mod metrics;
+pub mod openapi;
 pub mod status;
 pub mod verification;
 
 pub use self::verification::{
-    solidity::{multi_part, standard_json, version_list},
+    solidity::{metadata, multi_part, standard_json, version_list},
     sourcify,
 };
@@ -0,0 +1,104 @@
+use crate::http_server::{
+    error::{ApiError, ErrorCode},
+    handlers::verification::{
+        solidity::{
+            metadata, multi_part, standard_json,
+            types::{
+                MetadataRequest, MultiPartFiles, MultiPartFilesRequest, StandardJson,
+                StandardJsonRequest, VersionKind, VersionMetadata, VersionsResponse,
+            },
+            version_list,
+        },
+        sourcify::{
+            self, session,
+            types::{
+                ApiRequest, CheckResponse, ContractStatus, Files, ImportRequest, SessionContract,
+                SessionFilesRequest, SessionResponse, SessionVerifyContract, SessionVerifyRequest,
+            },
+        },
+        MatchType, VerificationResponse, VerificationResult, VerificationStatus,
+    },
+};
+use actix_web::{HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Verification", description = "Smart contracts verification service"),
+    paths(
+        multi_part::verify,
+        standard_json::verify,
+        metadata::verify,
+        version_list::get_version_list,
+        sourcify::verify,
+        sourcify::check,
+        sourcify::import,
+        session::create,
+        session::get,
+        session::add_files,
+        session::verify,
+        session::delete,
+    ),
+    components(schemas(
+        MultiPartFilesRequest,
+        MultiPartFiles,
+        StandardJsonRequest,
+        StandardJson,
+        MetadataRequest,
+        VersionsResponse,
+        VersionMetadata,
+        VersionKind,
+        ApiRequest,
+        Files,
+        CheckResponse,
+        ContractStatus,
+        ImportRequest,
+        SessionFilesRequest,
+        SessionVerifyRequest,
+        SessionVerifyContract,
+        SessionResponse,
+        SessionContract,
+        VerificationResponse,
+        VerificationResult,
+        MatchType,
+        VerificationStatus,
+        ApiError,
+        ErrorCode,
+    )),
+    tags(
+        (name = "solidity", description = "Verification by compiling the sources locally"),
+        (name = "sourcify", description = "Verification through Sourcify API"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Returns OpenAPI specification of the service.
+pub async fn openapi() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>Verification API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@4/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@4/swagger-ui-bundle.js"></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+/// Swagger UI page rendering the specification served at `/openapi.json`.
+pub async fn swagger_ui() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(SWAGGER_UI)
+}
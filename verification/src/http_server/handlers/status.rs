@@ -1,5 +1,186 @@
-use actix_web::{HttpResponse, Responder};
+use crate::{
+    compiler::{Compilers, VersionList},
+    http_server::handlers::sourcify::SourcifyApiClient,
+    solidity::{AnyFetcher, VersionsStatus},
+};
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
 
+/// Time the result of Sourcify health check is cached for,
+/// so that frequent probes don't make a request to Sourcify each.
+const SOURCIFY_CHECK_TTL: Duration = Duration::from_secs(30);
+
+/// Liveness probe. Succeeds while the server is able to answer requests.
 pub async fn status() -> impl Responder {
     HttpResponse::Ok().finish()
 }
+
+/// Subsystems the readiness of the service depends on.
+/// Disabled subsystems are not checked.
+pub struct Readiness {
+    compilers: Option<web::Data<Compilers<AnyFetcher>>>,
+    sourcify: Option<web::Data<SourcifyApiClient>>,
+    /// Result of the last Sourcify health check and the time it was made at.
+    /// Held while the check is made, so that concurrent probes wait for the single check.
+    sourcify_check: tokio::sync::Mutex<Option<(Instant, Result<(), String>)>>,
+}
+
+impl Readiness {
+    pub fn new(
+        compilers: Option<web::Data<Compilers<AnyFetcher>>>,
+        sourcify: Option<web::Data<SourcifyApiClient>>,
+    ) -> Self {
+        Self {
+            compilers,
+            sourcify,
+            sourcify_check: Default::default(),
+        }
+    }
+
+    async fn sourcify_health(&self, sourcify: &SourcifyApiClient) -> Result<(), String> {
+        let mut last_check = self.sourcify_check.lock().await;
+        match &*last_check {
+            Some((checked_at, result)) if checked_at.elapsed() < SOURCIFY_CHECK_TTL => {
+                result.clone()
+            }
+            _ => {
+                let result = sourcify.health_check().await.map_err(|err| err.to_string());
+                *last_check = Some((Instant::now(), result.clone()));
+                result
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub subsystems: BTreeMap<&'static str, SubsystemStatus>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SubsystemStatus {
+    pub ready: bool,
+    /// Subsystem is able to serve requests, but with limited functionality
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+    pub message: String,
+}
+
+impl SubsystemStatus {
+    fn from_result<E: std::fmt::Display>(result: Result<String, E>) -> Self {
+        match result {
+            Ok(message) => Self::ready(message),
+            Err(err) => Self {
+                ready: false,
+                degraded: false,
+                message: err.to_string(),
+            },
+        }
+    }
+
+    fn ready(message: String) -> Self {
+        Self {
+            ready: true,
+            degraded: false,
+            message,
+        }
+    }
+
+    fn degraded(message: String) -> Self {
+        Self {
+            ready: true,
+            degraded: true,
+            message,
+        }
+    }
+}
+
+/// Readiness probe. Succeeds only if all enabled subsystems are able to serve requests,
+/// otherwise returns 503 with the description of failed subsystems.
+///
+/// Sourcify is an external service, so its outage is reported as degraded and doesn't make
+/// the service unready: local verification still works and all replicas would be affected anyway.
+pub async fn readiness(readiness: web::Data<Readiness>) -> impl Responder {
+    let mut subsystems = BTreeMap::new();
+
+    if let Some(compilers) = &readiness.compilers {
+        let fetcher = compilers.fetcher();
+        let versions_list = match fetcher.versions_status() {
+            Some(versions_status) => versions_list_status(versions_status),
+            // backends without a list are ready as long as they have any compilers
+            None => {
+                let versions = compilers.all_versions().len();
+                SubsystemStatus::from_result(if versions == 0 {
+                    Err("no compilers available".to_string())
+                } else {
+                    Ok(format!("{} compilers available", versions))
+                })
+            }
+        };
+        subsystems.insert("compilers_list", versions_list);
+
+        let folder = match fetcher {
+            // compilers are only read from the folder
+            AnyFetcher::Local(_) => fetcher
+                .check_folder()
+                .await
+                .map(|_| "compilers folder is readable".to_string())
+                .map_err(|err| format!("compilers folder is not readable: {}", err)),
+            AnyFetcher::List(_) | AnyFetcher::Svm(_) => fetcher
+                .check_folder()
+                .await
+                .map(|_| "compilers folder is writable".to_string())
+                .map_err(|err| format!("compilers folder is not writable: {}", err)),
+        };
+        subsystems.insert("compilers_folder", SubsystemStatus::from_result(folder));
+    }
+
+    if let Some(sourcify) = &readiness.sourcify {
+        let api = match readiness.sourcify_health(sourcify).await {
+            Ok(()) => SubsystemStatus::ready("Sourcify API is reachable".to_string()),
+            Err(err) => SubsystemStatus::degraded(format!("Sourcify API is unreachable: {}", err)),
+        };
+        subsystems.insert("sourcify", api);
+    }
+
+    let ready = subsystems.values().all(|status| status.ready);
+    let response = ReadinessResponse { ready, subsystems };
+    if ready {
+        HttpResponse::Ok().json(response)
+    } else {
+        HttpResponse::ServiceUnavailable().json(response)
+    }
+}
+
+fn versions_list_status(versions_status: VersionsStatus) -> SubsystemStatus {
+    let last_refresh = versions_status
+        .last_refresh
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_else(|| "never".to_string());
+    if versions_status.is_degraded && versions_status.versions > 0 {
+        // contracts can still be verified with the compilers from the last known list
+        SubsystemStatus::degraded(format!(
+            "compilers list is unavailable, {} compilers from the last known list are used",
+            versions_status.versions
+        ))
+    } else {
+        SubsystemStatus::from_result(if versions_status.versions == 0 {
+            Err("compilers list is empty".to_string())
+        } else if versions_status.is_stale {
+            Err(format!(
+                "compilers list has not been refreshed since {}",
+                last_refresh
+            ))
+        } else {
+            Ok(format!(
+                "{} compilers available, last refreshed at {}",
+                versions_status.versions, last_refresh
+            ))
+        })
+    }
+}
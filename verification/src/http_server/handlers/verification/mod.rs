@@ -1,74 +1,30 @@
 #![allow(dead_code)]
 
-use ethers_solc::CompilerInput;
-use std::{collections::BTreeMap, fmt::Display};
+use std::fmt::Display;
 
-use crate::{compiler::CompilerVersion, solidity::VerificationSuccess, DisplayBytes};
+use crate::{
+    http_server::error::{ApiError, ErrorCode},
+    metrics,
+};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub use crate::types::{MatchType, VerificationResult};
 
 pub mod solidity;
 pub mod sourcify;
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub struct VerificationResponse {
     pub message: String,
     pub result: Option<VerificationResult>,
     pub status: VerificationStatus,
+    /// Reason of the verification failure. Is `None` for successful verifications.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
-pub struct VerificationResult {
-    pub file_name: String,
-    pub contract_name: String,
-    pub compiler_version: String,
-    pub evm_version: String,
-    pub constructor_arguments: Option<DisplayBytes>,
-    pub optimization: Option<bool>,
-    pub optimization_runs: Option<usize>,
-    pub contract_libraries: BTreeMap<String, String>,
-    pub abi: String,
-    pub sources: BTreeMap<String, String>,
-}
-
-impl From<(CompilerInput, CompilerVersion, VerificationSuccess)> for VerificationResult {
-    fn from(
-        (compiler_input, compiler_version, verification_success): (
-            CompilerInput,
-            CompilerVersion,
-            VerificationSuccess,
-        ),
-    ) -> Self {
-        VerificationResult {
-            file_name: verification_success.file_path,
-            contract_name: verification_success.contract_name,
-            compiler_version: compiler_version.to_string(),
-            evm_version: compiler_input
-                .settings
-                .evm_version
-                .map(|v| v.to_string())
-                .unwrap_or_else(|| "default".to_string()),
-            constructor_arguments: verification_success.constructor_args,
-            optimization: compiler_input.settings.optimizer.enabled,
-            optimization_runs: compiler_input.settings.optimizer.runs,
-            contract_libraries: compiler_input
-                .settings
-                .libraries
-                .libs
-                .into_iter()
-                .flat_map(|(_path, libs)| libs)
-                .collect(),
-            abi: serde_json::to_string(&verification_success.abi)
-                .expect("Is result of local compilation and, thus, should be always valid"),
-            sources: compiler_input
-                .sources
-                .into_iter()
-                .map(|(path, source)| (path.to_string_lossy().to_string(), source.content))
-                .collect(),
-        }
-    }
-}
-
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub enum VerificationStatus {
     #[serde(rename = "0")]
     Ok,
@@ -82,23 +38,44 @@ impl VerificationResponse {
             message: "OK".to_string(),
             result: Some(result),
             status: VerificationStatus::Ok,
+            code: None,
         }
     }
 
-    pub fn err(message: impl Display) -> Self {
+    pub fn err(code: ErrorCode, message: impl Display) -> Self {
         Self {
             message: message.to_string(),
             result: None,
             status: VerificationStatus::Failed,
+            code: Some(code),
         }
     }
 }
 
+/// Records the outcome of the verification request made through the `route`.
+pub(crate) fn count_verification_request(
+    route: &str,
+    response: &Result<VerificationResponse, ApiError>,
+) {
+    let status = match response {
+        Ok(VerificationResponse {
+            status: VerificationStatus::Ok,
+            ..
+        }) => "ok",
+        Ok(_) => "failed",
+        Err(_) => "error",
+    };
+    metrics::VERIFICATION_REQUESTS
+        .with_label_values(&[route, status])
+        .inc();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::parse::test_serialize_json_ok;
+    use crate::{tests::parse::test_serialize_json_ok, DisplayBytes};
     use serde_json::json;
+    use std::collections::BTreeMap;
 
     #[test]
     fn parse_response() {
@@ -123,6 +100,8 @@ mod tests {
                         }"#,
                     )
                     .unwrap(),
+                    match_type: None,
+                    storage_timestamp: None,
                 }),
                 json!({
                     "message": "OK",
@@ -147,11 +126,12 @@ mod tests {
                 }),
             ),
             (
-                VerificationResponse::err("Parse error"),
+                VerificationResponse::err(ErrorCode::CompilationFailed, "Parse error"),
                 json!({
                     "message": "Parse error",
                     "status": "1",
                     "result": null,
+                    "code": "COMPILATION_FAILED",
                 }),
             ),
         ])
@@ -0,0 +1,59 @@
+use super::types::MetadataRequest;
+use crate::{
+    compiler::{CompilerVersion, Compilers},
+    http_server::error::{ApiError, ErrorCode},
+    http_server::handlers::verification::{
+        count_verification_request, solidity::compile_and_verify_handler,
+        sourcify::metadata::MetadataError, VerificationResponse,
+    },
+    solidity::{AnyFetcher, Input},
+};
+use actix_web::web::{self, Json};
+use std::str::FromStr;
+
+/// Compiles the sources with the compiler and settings from metadata.json
+/// and verifies the resulting bytecode, without calling Sourcify
+#[utoipa::path(
+    post,
+    operation_id = "verify_metadata",
+    path = "/api/v1/solidity/verify/metadata",
+    request_body = MetadataRequest,
+    responses(
+        (status = 200, description = "Verification has been finished", body = VerificationResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "solidity"
+)]
+pub async fn verify(
+    compilers: web::Data<Compilers<AnyFetcher>>,
+    params: Json<MetadataRequest>,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    let response = verify_metadata(&compilers, params.into_inner()).await;
+    count_verification_request("metadata", &response);
+    response.map(Json)
+}
+
+pub(crate) async fn verify_metadata(
+    compilers: &Compilers<AnyFetcher>,
+    params: MetadataRequest,
+) -> Result<VerificationResponse, ApiError> {
+    let invalid_metadata = |err: MetadataError| ApiError::new(ErrorCode::InvalidMetadata, err);
+    let (metadata, files) = params
+        .files
+        .extract_metadata_and_source_files()
+        .map_err(invalid_metadata)?;
+    let sources = metadata.validate_sources(files).map_err(invalid_metadata)?;
+    // metadata contains the exact version the contract was compiled with
+    let compiler_version = CompilerVersion::from_str(&metadata.compiler.version)
+        .map_err(|err| ApiError::new(ErrorCode::InvalidCompilerVersion, err))?;
+    let compiler_input = metadata.compiler_input(sources).map_err(invalid_metadata)?;
+    let input = Input {
+        compiler_version,
+        compiler_input,
+        creation_tx_input: &params.creation_bytecode,
+        deployed_bytecode: &params.deployed_bytecode,
+        bruteforce_bytecode_hashes: false,
+    };
+    compile_and_verify_handler(compilers, input).await
+}
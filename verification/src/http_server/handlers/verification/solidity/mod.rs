@@ -1,6 +1,37 @@
-mod contract_verifier;
-mod types;
+pub(crate) mod types;
 
+pub mod metadata;
 pub mod multi_part;
 pub mod standard_json;
 pub mod version_list;
+
+use crate::{
+    compiler::{Compilers, CompilersError, Fetcher},
+    http_server::error::{ApiError, ErrorCode},
+    solidity::{self, Input},
+    VerificationResponse,
+};
+use std::fmt::{Debug, Display};
+
+/// Verifies the contract, reporting compilation and bytecode mismatch errors
+/// as failed verifications, and other errors as errors of the request.
+pub(crate) async fn compile_and_verify_handler<T: Fetcher>(
+    compilers: &Compilers<T>,
+    input: Input<'_>,
+) -> Result<VerificationResponse, ApiError>
+where
+    <T as Fetcher>::Error: Debug + Display,
+{
+    match solidity::verify(compilers, input).await {
+        Ok(result) => Ok(VerificationResponse::ok(result)),
+        Err(err @ solidity::Error::Compilers(CompilersError::Compilation(_))) => {
+            Ok(VerificationResponse::err(ErrorCode::CompilationFailed, err))
+        }
+        Err(solidity::Error::NoMatchingContracts(closest_error)) => Ok(VerificationResponse::err(
+            (&closest_error).into(),
+            solidity::Error::NoMatchingContracts(closest_error),
+        )),
+        Err(solidity::Error::Initialization(err)) => Err(err.into()),
+        Err(solidity::Error::Compilers(err)) => Err(err.into()),
+    }
+}
@@ -1,35 +1,52 @@
 use super::types::{MultiPartFiles, VerificationRequest};
 use crate::{
-    compiler::{CompilerVersion, Compilers},
+    compiler::{CompilerVersion, Compilers, VersionList},
+    http_server::error::{ApiError, ErrorCode},
     http_server::handlers::verification::{
-        solidity::contract_verifier::{compile_and_verify_handler, Input},
-        VerificationResponse,
+        count_verification_request, solidity::compile_and_verify_handler, VerificationResponse,
     },
-    solidity::CompilerFetcher,
+    solidity::{AnyFetcher, Input},
 };
-use actix_web::{
-    error,
-    web::{self, Json},
-    Error,
-};
-use std::str::FromStr;
+use actix_web::web::{self, Json};
 
+/// Compiles the provided source files and verifies the resulting bytecode
+#[utoipa::path(
+    post,
+    operation_id = "verify_multiple_files",
+    path = "/api/v1/solidity/verify/multiple-files",
+    request_body = MultiPartFilesRequest,
+    responses(
+        (status = 200, description = "Verification has been finished", body = VerificationResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "solidity"
+)]
 pub async fn verify(
-    compilers: web::Data<Compilers<CompilerFetcher>>,
+    compilers: web::Data<Compilers<AnyFetcher>>,
     params: Json<VerificationRequest<MultiPartFiles>>,
-) -> Result<Json<VerificationResponse>, Error> {
-    let params = params.into_inner();
+) -> Result<Json<VerificationResponse>, ApiError> {
+    let response = verify_multi_part(&compilers, params.into_inner()).await;
+    count_verification_request("multiple-files", &response);
+    response.map(Json)
+}
 
-    let compiler_input = params.content.try_into().map_err(error::ErrorBadRequest)?;
+pub(crate) async fn verify_multi_part(
+    compilers: &Compilers<AnyFetcher>,
+    params: VerificationRequest<MultiPartFiles>,
+) -> Result<VerificationResponse, ApiError> {
+    let compiler_input = params
+        .content
+        .try_into()
+        .map_err(|err| ApiError::new(ErrorCode::InvalidCompilerInput, err))?;
     let compiler_version =
-        CompilerVersion::from_str(&params.compiler_version).map_err(error::ErrorBadRequest)?;
+        CompilerVersion::resolve(&params.compiler_version, &compilers.all_versions())?;
     let input = Input {
         compiler_version,
         compiler_input,
         creation_tx_input: &params.creation_bytecode,
         deployed_bytecode: &params.deployed_bytecode,
+        bruteforce_bytecode_hashes: true,
     };
-    compile_and_verify_handler(&compilers, input, true)
-        .await
-        .map(Json)
+    compile_and_verify_handler(compilers, input).await
 }
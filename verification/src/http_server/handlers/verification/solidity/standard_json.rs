@@ -1,38 +1,51 @@
 use super::types::VerificationRequest;
 use crate::{
-    compiler::{CompilerVersion, Compilers},
+    compiler::{CompilerVersion, Compilers, VersionList},
+    http_server::error::ApiError,
     http_server::handlers::verification::{
-        solidity::{
-            contract_verifier::{compile_and_verify_handler, Input},
-            types::StandardJson,
-        },
+        count_verification_request,
+        solidity::{compile_and_verify_handler, types::StandardJson},
         VerificationResponse,
     },
-    solidity::CompilerFetcher,
+    solidity::{AnyFetcher, Input},
 };
-use actix_web::{
-    error,
-    web::{self, Json},
-    Error,
-};
-use std::str::FromStr;
+use actix_web::web::{self, Json};
 
+/// Compiles the provided standard JSON input and verifies the resulting bytecode
+#[utoipa::path(
+    post,
+    operation_id = "verify_standard_json",
+    path = "/api/v1/solidity/verify/standard-json",
+    request_body = StandardJsonRequest,
+    responses(
+        (status = 200, description = "Verification has been finished", body = VerificationResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "solidity"
+)]
 pub async fn verify(
-    compilers: web::Data<Compilers<CompilerFetcher>>,
+    compilers: web::Data<Compilers<AnyFetcher>>,
     params: Json<VerificationRequest<StandardJson>>,
-) -> Result<Json<VerificationResponse>, Error> {
-    let params = params.into_inner();
+) -> Result<Json<VerificationResponse>, ApiError> {
+    let response = verify_standard_json(&compilers, params.into_inner()).await;
+    count_verification_request("standard-json", &response);
+    response.map(Json)
+}
 
+pub(crate) async fn verify_standard_json(
+    compilers: &Compilers<AnyFetcher>,
+    params: VerificationRequest<StandardJson>,
+) -> Result<VerificationResponse, ApiError> {
     let compiler_input = params.content.into();
     let compiler_version =
-        CompilerVersion::from_str(&params.compiler_version).map_err(error::ErrorBadRequest)?;
+        CompilerVersion::resolve(&params.compiler_version, &compilers.all_versions())?;
     let input = Input {
         compiler_version,
         compiler_input,
         creation_tx_input: &params.creation_bytecode,
         deployed_bytecode: &params.deployed_bytecode,
+        bruteforce_bytecode_hashes: false,
     };
-    compile_and_verify_handler(&compilers, input, false)
-        .await
-        .map(Json)
+    compile_and_verify_handler(compilers, input).await
 }
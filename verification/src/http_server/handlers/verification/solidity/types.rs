@@ -1,11 +1,19 @@
+use crate::http_server::handlers::sourcify::types::Files;
 use ethers_solc::{
     artifacts::{Libraries, Settings, Source, Sources},
     CompilerInput, EvmVersion,
 };
+use primitive_types::H256;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+use url::Url;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, ToSchema)]
+#[aliases(
+    MultiPartFilesRequest = VerificationRequest<MultiPartFiles>,
+    StandardJsonRequest = VerificationRequest<StandardJson>
+)]
 pub struct VerificationRequest<T> {
     pub deployed_bytecode: String,
     pub creation_bytecode: String,
@@ -15,12 +23,13 @@ pub struct VerificationRequest<T> {
     pub content: T,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, ToSchema)]
 pub struct MultiPartFiles {
-    sources: BTreeMap<PathBuf, String>,
-    evm_version: String,
-    optimization_runs: Option<usize>,
-    contract_libraries: Option<BTreeMap<String, String>>,
+    #[schema(value_type = BTreeMap<String, String>)]
+    pub sources: BTreeMap<PathBuf, String>,
+    pub evm_version: String,
+    pub optimization_runs: Option<usize>,
+    pub contract_libraries: Option<BTreeMap<String, String>>,
 }
 
 impl TryFrom<MultiPartFiles> for CompilerInput {
@@ -35,8 +44,8 @@ impl TryFrom<MultiPartFiles> for CompilerInput {
             // so we assume that every file MAY contains all libraries
             let libs = multi_part
                 .sources
-                .iter()
-                .map(|(filename, _)| (PathBuf::from(filename), libs.clone()))
+                .keys()
+                .map(|filename| (PathBuf::from(filename), libs.clone()))
                 .collect();
             settings.libraries = Libraries { libs };
         }
@@ -62,9 +71,20 @@ impl TryFrom<MultiPartFiles> for CompilerInput {
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, ToSchema)]
 pub struct StandardJson {
-    input: CompilerInput,
+    /// Standard JSON input as accepted by the compiler
+    #[schema(value_type = Object)]
+    pub input: CompilerInput,
+}
+
+/// Sources and metadata.json generated by the compiler for the contract.
+/// The contract is compiled with the compiler and settings from the metadata.
+#[derive(Debug, Deserialize, PartialEq, ToSchema)]
+pub struct MetadataRequest {
+    pub deployed_bytecode: String,
+    pub creation_bytecode: String,
+    pub files: Files,
 }
 
 impl From<StandardJson> for CompilerInput {
@@ -73,9 +93,45 @@ impl From<StandardJson> for CompilerInput {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionKind {
+    Release,
+    Nightly,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VersionsQuery {
+    /// Return only versions of the specified kind
+    pub kind: Option<VersionKind>,
+    /// Return only versions matching the semver requirement (e.g. `^0.8`)
+    pub version: Option<String>,
+    /// Include metadata of the versions into the response
+    #[serde(default)]
+    pub metadata: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct VersionsResponse {
     pub versions: Vec<String>,
+    /// Metadata of the versions in the same order. Returned only if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Vec<VersionMetadata>>,
+}
+
+#[derive(Debug, Serialize, PartialEq, ToSchema)]
+pub struct VersionMetadata {
+    pub version: String,
+    pub kind: VersionKind,
+    /// Build date of nightly versions
+    pub date: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub download_url: Option<Url>,
+    #[schema(value_type = Option<String>)]
+    pub sha256: Option<H256>,
+    /// Whether the compiler is already available locally
+    pub downloaded: bool,
 }
 
 #[cfg(test)]
@@ -211,7 +267,7 @@ mod tests {
         }"#;
 
         let deserialized: VerificationRequest<StandardJson> =
-            serde_json::from_str(&input).expect("Valid json");
+            serde_json::from_str(input).expect("Valid json");
         assert_eq!(
             deserialized.deployed_bytecode, "0x6001",
             "Invalid deployed bytecode"
@@ -1,21 +1,91 @@
-use super::types::VersionsResponse;
+use super::types::{VersionKind, VersionMetadata, VersionsQuery, VersionsResponse};
 use crate::{
-    compiler::{Compilers, VersionList},
-    solidity::CompilerFetcher,
-};
-
-use actix_web::{
-    web::{self, Json},
-    Error,
+    compiler::{CompilerVersion, Compilers, VersionList},
+    http_server::error::{ApiError, ErrorCode},
+    solidity::AnyFetcher,
 };
+use actix_web::web::{self, Json};
+use semver::VersionReq;
+use std::collections::HashSet;
 
+/// Returns the list of available compiler versions
+#[utoipa::path(
+    get,
+    operation_id = "get_version_list",
+    path = "/api/v1/solidity/versions",
+    params(VersionsQuery),
+    responses(
+        (status = 200, description = "Versions sorted in descending order", body = VersionsResponse),
+        (status = 400, description = "Invalid query", body = ApiError),
+    ),
+    tag = "solidity"
+)]
 pub async fn get_version_list(
-    compilers: web::Data<Compilers<CompilerFetcher>>,
-) -> Result<Json<VersionsResponse>, Error> {
-    let mut versions = compilers.all_versions();
+    compilers: web::Data<Compilers<AnyFetcher>>,
+    query: web::Query<VersionsQuery>,
+) -> Result<Json<VersionsResponse>, ApiError> {
+    list_versions(&compilers, &query).await.map(Json)
+}
+
+pub(crate) async fn list_versions(
+    compilers: &Compilers<AnyFetcher>,
+    query: &VersionsQuery,
+) -> Result<VersionsResponse, ApiError> {
+    let requirement = query
+        .version
+        .as_deref()
+        .map(VersionReq::parse)
+        .transpose()
+        .map_err(|err| ApiError::new(ErrorCode::InvalidRequest, err))?;
+
+    let mut versions: Vec<_> = compilers
+        .all_versions()
+        .into_iter()
+        .filter(|version| query.kind.is_none_or(|kind| kind == version_kind(version)))
+        .filter(|version| {
+            requirement
+                .as_ref()
+                .is_none_or(|requirement| requirement.matches(version.version()))
+        })
+        .collect();
     // sort in descending order
     versions.sort_by(|x, y| x.cmp(y).reverse());
+
+    let metadata = if query.metadata {
+        let downloaded = compilers.fetcher().downloaded_versions().await;
+        Some(
+            versions
+                .iter()
+                .map(|version| version_metadata(compilers, &downloaded, version))
+                .collect(),
+        )
+    } else {
+        None
+    };
     let versions = versions.into_iter().map(|v| v.to_string()).collect();
 
-    Ok(Json(VersionsResponse { versions }))
+    Ok(VersionsResponse { versions, metadata })
+}
+
+fn version_kind(version: &CompilerVersion) -> VersionKind {
+    match version {
+        CompilerVersion::Release(_) => VersionKind::Release,
+        CompilerVersion::Nightly(_) => VersionKind::Nightly,
+    }
+}
+
+fn version_metadata(
+    compilers: &Compilers<AnyFetcher>,
+    downloaded: &HashSet<CompilerVersion>,
+    version: &CompilerVersion,
+) -> VersionMetadata {
+    let info = compilers.fetcher().compiler_info(version);
+    VersionMetadata {
+        version: version.to_string(),
+        kind: version_kind(version),
+        date: version.date().map(|date| date.to_string()),
+        download_url: info.as_ref().map(|info| info.url.clone()),
+        sha256: info.map(|info| info.sha256),
+        downloaded: downloaded.contains(version),
+    }
 }
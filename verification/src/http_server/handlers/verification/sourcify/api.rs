@@ -1,10 +1,18 @@
-use crate::{VerificationResponse, VerificationResult};
-use actix_web::{error, error::Error};
-use futures::Future;
-use reqwest::Url;
-use std::{num::NonZeroUsize, sync::Arc};
+use crate::{
+    http_server::error::{ApiError, ErrorCode},
+    metrics, MatchType, VerificationResponse, VerificationResult,
+};
+use rand::Rng;
+use reqwest::{header, RequestBuilder, Response, StatusCode, Url};
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+use tokio::time::Instant;
 
-use super::types::{ApiFilesResponse, ApiRequest, ApiVerificationResponse, Files};
+use super::chains::Chains;
+use super::types::{
+    ApiChain, ApiCheckResponse, ApiFilesResponse, ApiRequest, ApiSessionResponse,
+    ApiSessionVerifyContract, ApiVerificationResponse, CheckQuery, CheckResponse, ContractStatus,
+    Files, ImportRequest, ResultItem,
+};
 
 #[async_trait::async_trait]
 pub(super) trait SourcifyApi {
@@ -15,41 +23,220 @@ pub(super) trait SourcifyApi {
 
     async fn source_files_request(
         &self,
-        params: &ApiRequest,
+        chain: &str,
+        address: &str,
+        match_type: MatchType,
     ) -> Result<ApiFilesResponse, reqwest::Error>;
+
+    async fn check_request(
+        &self,
+        chain: &str,
+        addresses: &[&str],
+    ) -> Result<ApiCheckResponse, reqwest::Error>;
+
+    async fn chains_request(&self) -> Result<Vec<ApiChain>, reqwest::Error>;
+
+    /// Uploads the files into the session identified by the `cookie`
+    /// or into a new session if the cookie is missing.
+    async fn session_files_request(
+        &self,
+        cookie: Option<&str>,
+        files: &Files,
+    ) -> Result<ApiSession, reqwest::Error>;
+
+    async fn session_data_request(&self, cookie: &str) -> Result<ApiSession, reqwest::Error>;
+
+    async fn session_verify_request(
+        &self,
+        cookie: &str,
+        contracts: &[ApiSessionVerifyContract<'_>],
+    ) -> Result<ApiSession, reqwest::Error>;
+
+    async fn session_clear_request(&self, cookie: &str) -> Result<(), reqwest::Error>;
+
+    fn chains(&self) -> &Chains;
+}
+
+/// Response of Sourcify session API along with the cookie identifying the session,
+/// if Sourcify has set it.
+pub(super) struct ApiSession {
+    pub response: ApiSessionResponse,
+    pub cookie: Option<String>,
+}
+
+/// How failed requests to Sourcify API are retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts made for a request.
+    pub attempts: NonZeroUsize,
+    /// Delay before the first retry; doubled after each failed attempt.
+    pub initial_delay: Duration,
+    /// Maximum delay between attempts, including the jitter
+    /// (not applied to delays requested by `Retry-After`).
+    pub max_delay: Duration,
+    /// Total time all attempts of a request may take.
+    pub deadline: Duration,
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the retry following the `attempt` (starting from 1).
+    /// The delay requested by the server takes precedence over the exponential backoff.
+    fn delay(&self, attempt: usize, retry_after: Option<Duration>) -> Duration {
+        retry_after.unwrap_or_else(|| {
+            let backoff = self
+                .initial_delay
+                .saturating_mul(2u32.saturating_pow(attempt as u32 - 1))
+                .min(self.max_delay);
+            let jitter = rand::thread_rng().gen_range(Duration::ZERO..=backoff / 2);
+            (backoff + jitter).min(self.max_delay)
+        })
+    }
 }
 
 pub struct SourcifyApiClient {
     host: Url,
-    request_timeout: u64,
-    verification_attempts: NonZeroUsize,
+    client: reqwest::Client,
+    retry_policy: RetryPolicy,
+    chains: Chains,
 }
 
 impl SourcifyApiClient {
-    pub fn new(host: Url, request_timeout: u64, verification_attempts: NonZeroUsize) -> Self {
+    pub fn new(host: Url, request_timeout: u64, retry_policy: RetryPolicy, chains: Chains) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(request_timeout))
+            .build()
+            .expect("client configuration should be valid");
         Self {
             host,
-            request_timeout,
-            verification_attempts,
+            client,
+            retry_policy,
+            chains,
         }
     }
-}
 
-pub async fn make_retrying_request<F, Fut, Response, Error>(
-    attempts: NonZeroUsize,
-    request: F,
-) -> Result<Response, Error>
-where
-    F: Fn() -> Fut,
-    Fut: Future<Output = Result<Response, Error>>,
-{
-    for _ in 0..attempts.get() - 1 {
-        let resp = request().await;
-        if resp.is_ok() {
-            return resp;
+    /// Checks that Sourcify API is reachable and reports itself as healthy.
+    pub async fn health_check(&self) -> Result<(), reqwest::Error> {
+        let url = self.host.join("health").expect("should be valid url");
+        self.client.get(url).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    /// Sends the request built by `request`, retrying transient failures according to
+    /// the retry policy. `request_name` is used to distinguish the requests in metrics.
+    /// Requests which are not `idempotent` are retried only if Sourcify certainly
+    /// hasn't processed them (see [`is_transient_error`] and [`is_transient_status`]).
+    ///
+    /// Responses with other statuses are returned as is, since Sourcify describes
+    /// failed verifications in the bodies of its 4xx responses.
+    async fn send_with_retries(
+        &self,
+        request_name: &str,
+        idempotent: bool,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<Response, reqwest::Error> {
+        let deadline = Instant::now() + self.retry_policy.deadline;
+        let mut attempt = 1;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let result = {
+                let _timer = metrics::SOURCIFY_REQUEST_TIME
+                    .with_label_values(&[request_name])
+                    .start_timer();
+                // the client timeout still applies if it is shorter
+                request().timeout(remaining).send().await
+            };
+            let retry_after = match &result {
+                Ok(response) if is_transient_status(response.status(), idempotent) => {
+                    Some(retry_after(response))
+                }
+                Err(err) if is_transient_error(err, idempotent) => Some(None),
+                _ => None,
+            };
+            let delay = match retry_after {
+                Some(retry_after) if attempt < self.retry_policy.attempts.get() => {
+                    self.retry_policy.delay(attempt, retry_after)
+                }
+                _ => return result,
+            };
+            if Instant::now() + delay >= deadline {
+                log::warn!(
+                    "request '{}' to Sourcify failed, no time left to retry it",
+                    request_name
+                );
+                return result;
+            }
+            log::warn!(
+                "attempt {} of request '{}' to Sourcify failed, retrying in {:?}",
+                attempt,
+                request_name,
+                delay
+            );
+            metrics::SOURCIFY_RETRIES
+                .with_label_values(&[request_name])
+                .inc();
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
     }
-    request().await
+}
+
+impl SourcifyApiClient {
+    /// Sends the session request with the `cookie`, returning the cookie
+    /// of the session if Sourcify sets a new one.
+    async fn session_request(
+        &self,
+        request_name: &str,
+        idempotent: bool,
+        cookie: Option<&str>,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<ApiSession, reqwest::Error> {
+        let response = self
+            .send_with_retries(request_name, idempotent, || match cookie {
+                Some(cookie) => request().header(header::COOKIE, cookie),
+                None => request(),
+            })
+            .await?;
+        let cookie = session_cookie(&response);
+        Ok(ApiSession {
+            response: response.json().await?,
+            cookie,
+        })
+    }
+}
+
+/// Joins the cookies set by the response into the value of `Cookie` header.
+fn session_cookie(response: &Response) -> Option<String> {
+    let cookies: Vec<_> = response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| value.split(';').next())
+        .map(str::trim)
+        .collect();
+    (!cookies.is_empty()).then(|| cookies.join("; "))
+}
+
+/// Connection failures are retried, as the request hasn't reached the server.
+/// Timeouts are retried for idempotent requests only, since the timed out request
+/// may have been processed. Other errors (e.g. invalid urls or redirect loops)
+/// are not going to disappear.
+fn is_transient_error(err: &reqwest::Error, idempotent: bool) -> bool {
+    err.is_connect() || (idempotent && err.is_timeout())
+}
+
+/// Rate limiting and unavailability are retried, as the server has refused the request.
+/// Other server errors are retried for idempotent requests only.
+fn is_transient_status(status: StatusCode, idempotent: bool) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::SERVICE_UNAVAILABLE
+        || (idempotent && status.is_server_error())
+}
+
+/// Parses the delay of `Retry-After` header. Only the number of seconds is supported.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(header::RETRY_AFTER)?.to_str().ok()?;
+    value.trim().parse().ok().map(Duration::from_secs)
 }
 
 #[async_trait::async_trait]
@@ -58,60 +245,372 @@ impl SourcifyApi for SourcifyApiClient {
         &self,
         params: &ApiRequest,
     ) -> Result<ApiVerificationResponse, reqwest::Error> {
-        make_retrying_request(self.verification_attempts, || async {
-            let resp = reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(self.request_timeout))
-                .build()?
-                .post(self.host.as_str())
-                .json(&params)
-                .send()
-                .await?;
-            resp.json().await
+        self.send_with_retries("verify", false, || {
+            self.client.post(self.host.as_str()).json(&params)
         })
+        .await?
+        .json()
         .await
     }
 
     async fn source_files_request(
         &self,
-        params: &ApiRequest,
+        chain: &str,
+        address: &str,
+        match_type: MatchType,
     ) -> Result<ApiFilesResponse, reqwest::Error> {
-        make_retrying_request(self.verification_attempts, || async {
-            let url = self
-                .host
-                .join(format!("files/any/{}/{}", &params.chain, &params.address).as_str())
-                .expect("should be valid url");
-            let resp = reqwest::get(url).await?;
-
-            resp.json().await
+        let repository = match match_type {
+            MatchType::Perfect => "full",
+            MatchType::Partial => "partial",
+        };
+        let url = self
+            .host
+            .join(format!("files/{}/{}/{}", repository, chain, address).as_str())
+            .expect("should be valid url");
+        self.send_with_retries("files", true, || self.client.get(url.clone()))
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    async fn check_request(
+        &self,
+        chain: &str,
+        addresses: &[&str],
+    ) -> Result<ApiCheckResponse, reqwest::Error> {
+        let url = self
+            .host
+            .join("check-by-addresses")
+            .expect("should be valid url");
+        let addresses = addresses.join(",");
+        self.send_with_retries("check", true, || {
+            self.client
+                .get(url.clone())
+                .query(&[("addresses", addresses.as_str()), ("chainIds", chain)])
+        })
+        .await?
+        .json()
+        .await
+    }
+
+    /// Not retried: the request is made before the ones of the users, and the failure
+    /// is cached by [`Chains`], so users don't wait for the unavailable Sourcify twice.
+    async fn chains_request(&self) -> Result<Vec<ApiChain>, reqwest::Error> {
+        let url = self.host.join("chains").expect("should be valid url");
+        let response = {
+            let _timer = metrics::SOURCIFY_REQUEST_TIME
+                .with_label_values(&["chains"])
+                .start_timer();
+            self.client.get(url).send().await?
+        };
+        response.error_for_status()?.json().await
+    }
+
+    async fn session_files_request(
+        &self,
+        cookie: Option<&str>,
+        files: &Files,
+    ) -> Result<ApiSession, reqwest::Error> {
+        let url = self
+            .host
+            .join("session/input-files")
+            .expect("should be valid url");
+        self.session_request("session_files", false, cookie, || {
+            self.client
+                .post(url.clone())
+                .json(&serde_json::json!({ "files": files }))
         })
         .await
     }
+
+    async fn session_data_request(&self, cookie: &str) -> Result<ApiSession, reqwest::Error> {
+        let url = self.host.join("session/data").expect("should be valid url");
+        self.session_request("session_data", true, Some(cookie), || {
+            self.client.get(url.clone())
+        })
+        .await
+    }
+
+    async fn session_verify_request(
+        &self,
+        cookie: &str,
+        contracts: &[ApiSessionVerifyContract<'_>],
+    ) -> Result<ApiSession, reqwest::Error> {
+        let url = self
+            .host
+            .join("session/verify-validated")
+            .expect("should be valid url");
+        self.session_request("session_verify", false, Some(cookie), || {
+            self.client
+                .post(url.clone())
+                .json(&serde_json::json!({ "contracts": contracts }))
+        })
+        .await
+    }
+
+    async fn session_clear_request(&self, cookie: &str) -> Result<(), reqwest::Error> {
+        let url = self
+            .host
+            .join("session/clear")
+            .expect("should be valid url");
+        // clearing the session twice is harmless
+        self.send_with_retries("session_clear", true, || {
+            self.client.post(url.clone()).header(header::COOKIE, cookie)
+        })
+        .await?
+        .error_for_status()?;
+        Ok(())
+    }
+
+    fn chains(&self) -> &Chains {
+        &self.chains
+    }
 }
 
 pub(super) async fn verify_using_sourcify_client(
     sourcify_client: Arc<impl SourcifyApi>,
-    params: ApiRequest,
-) -> Result<VerificationResponse, Error> {
+    mut params: ApiRequest,
+) -> Result<VerificationResponse, ApiError> {
+    params.chain = sourcify_client
+        .chains()
+        .resolve(sourcify_client.as_ref(), &params.chain)
+        .await?;
     let response = sourcify_client
         .verification_request(&params)
         .await
-        .map_err(error::ErrorInternalServerError)?;
+        .map_err(|err| ApiError::new(ErrorCode::SourcifyUnavailable, err))?;
 
     match response {
-        ApiVerificationResponse::Verified { result: _ } => {
-            let api_files_response = sourcify_client
-                .source_files_request(&params)
-                .await
-                .map_err(error::ErrorInternalServerError)?;
-            let files =
-                Files::try_from(api_files_response).map_err(error::ErrorInternalServerError)?;
-            let result = VerificationResult::try_from(files).map_err(error::ErrorBadRequest)?;
+        ApiVerificationResponse::Verified { result } => {
+            let item = verified_contract(result, &params.address)?;
+            let match_type = match_type(&item.status)?.ok_or_else(|| {
+                ApiError::new(
+                    ErrorCode::SourcifyInvalidResponse,
+                    "verified contract has no match",
+                )
+            })?;
+            let mut result = verified_result(
+                sourcify_client.as_ref(),
+                &params.chain,
+                &params.address,
+                match_type,
+            )
+            .await?;
+            result.storage_timestamp = item.storage_timestamp;
             Ok(VerificationResponse::ok(result))
         }
-        ApiVerificationResponse::Error { error } => Ok(VerificationResponse::err(error)),
+        ApiVerificationResponse::Error { error } => Ok(VerificationResponse::err(
+            ErrorCode::SourcifyVerificationFailed,
+            error,
+        )),
         ApiVerificationResponse::ValidationErrors { message, errors } => {
             let error_message = format!("{}: {:?}", message, errors);
-            Err(error::ErrorBadRequest(error_message))
+            Err(ApiError::new(
+                ErrorCode::SourcifyValidationFailed,
+                error_message,
+            ))
         }
     }
 }
+
+pub(super) async fn check_using_sourcify_client(
+    sourcify_client: Arc<impl SourcifyApi>,
+    mut query: CheckQuery,
+) -> Result<CheckResponse, ApiError> {
+    query.chain = sourcify_client
+        .chains()
+        .resolve(sourcify_client.as_ref(), &query.chain)
+        .await?;
+    let addresses: Vec<_> = query
+        .addresses
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .collect();
+    if addresses.is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "at least one address should be specified",
+        ));
+    }
+
+    let response = sourcify_client
+        .check_request(&query.chain, &addresses)
+        .await
+        .map_err(|err| ApiError::new(ErrorCode::SourcifyUnavailable, err))?;
+    match response {
+        ApiCheckResponse::Checked(items) => {
+            let contracts = items
+                .into_iter()
+                .map(|item| {
+                    Ok(ContractStatus {
+                        match_type: match_type(&item.status)?,
+                        address: item.address,
+                    })
+                })
+                .collect::<Result<_, ApiError>>()?;
+            Ok(CheckResponse { contracts })
+        }
+        ApiCheckResponse::ValidationErrors { message, errors } => Err(ApiError::new(
+            ErrorCode::SourcifyValidationFailed,
+            format!("{}: {:?}", message, errors),
+        )),
+    }
+}
+
+pub(super) async fn import_using_sourcify_client(
+    sourcify_client: Arc<impl SourcifyApi>,
+    mut params: ImportRequest,
+) -> Result<VerificationResponse, ApiError> {
+    params.chain = sourcify_client
+        .chains()
+        .resolve(sourcify_client.as_ref(), &params.chain)
+        .await?;
+    let query = CheckQuery {
+        chain: params.chain.clone(),
+        addresses: params.address.clone(),
+    };
+    let match_type = check_using_sourcify_client(sourcify_client.clone(), query)
+        .await?
+        .contracts
+        .into_iter()
+        .find(|status| status.address.eq_ignore_ascii_case(&params.address))
+        .and_then(|status| status.match_type);
+    match match_type {
+        Some(match_type) => {
+            let result = verified_result(
+                sourcify_client.as_ref(),
+                &params.chain,
+                &params.address,
+                match_type,
+            )
+            .await?;
+            Ok(VerificationResponse::ok(result))
+        }
+        None => Ok(VerificationResponse::err(
+            ErrorCode::SourcifyContractNotVerified,
+            format!(
+                "contract {} is not verified by Sourcify on chain {}",
+                params.address, params.chain
+            ),
+        )),
+    }
+}
+
+/// Builds the result from the files of the verified contract stored by Sourcify.
+async fn verified_result(
+    sourcify_client: &impl SourcifyApi,
+    chain: &str,
+    address: &str,
+    match_type: MatchType,
+) -> Result<VerificationResult, ApiError> {
+    let api_files_response = sourcify_client
+        .source_files_request(chain, address, match_type)
+        .await
+        .map_err(|err| ApiError::new(ErrorCode::SourcifyUnavailable, err))?;
+    let files = Files::try_from(api_files_response)
+        .map_err(|err| ApiError::new(ErrorCode::SourcifyInvalidResponse, err))?;
+    let mut result = VerificationResult::try_from(files)
+        .map_err(|err| ApiError::new(ErrorCode::InvalidMetadata, err))?;
+    result.match_type = Some(match_type);
+    Ok(result)
+}
+
+/// Parses the match status reported by Sourcify; `None` means the contract is not verified.
+pub(super) fn match_type(status: &str) -> Result<Option<MatchType>, ApiError> {
+    match status {
+        "perfect" => Ok(Some(MatchType::Perfect)),
+        "partial" => Ok(Some(MatchType::Partial)),
+        "false" => Ok(None),
+        status => Err(ApiError::new(
+            ErrorCode::SourcifyInvalidResponse,
+            format!("unknown match status: {}", status),
+        )),
+    }
+}
+
+/// Picks the result item of the requested contract, as Sourcify may return several items.
+fn verified_contract(result: Vec<ResultItem>, address: &str) -> Result<ResultItem, ApiError> {
+    result
+        .into_iter()
+        .find(|item| item.address.eq_ignore_ascii_case(address))
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::SourcifyInvalidResponse,
+                format!("no result for contract {} in the response", address),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delays() {
+        let policy = RetryPolicy {
+            attempts: NonZeroUsize::new(5).unwrap(),
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(3),
+            deadline: Duration::from_secs(30),
+        };
+        for (attempt, backoff) in [(1, 1), (2, 2), (3, 3), (4, 3), (10, 3)] {
+            let backoff = Duration::from_secs(backoff);
+            let delay = policy.delay(attempt, None);
+            assert!(
+                backoff <= delay && delay <= backoff * 3 / 2,
+                "attempt: {}, delay: {:?}",
+                attempt,
+                delay
+            );
+            assert!(
+                delay <= policy.max_delay,
+                "attempt: {}, delay: {:?}",
+                attempt,
+                delay
+            );
+        }
+        // the delay requested by the server is used as is
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(10))),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn retries_non_idempotent_requests_only_if_refused() {
+        for status in [
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            assert!(is_transient_status(status, false), "{}", status);
+            assert!(is_transient_status(status, true), "{}", status);
+        }
+        for status in [
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::GATEWAY_TIMEOUT,
+        ] {
+            assert!(!is_transient_status(status, false), "{}", status);
+            assert!(is_transient_status(status, true), "{}", status);
+        }
+        assert!(!is_transient_status(StatusCode::BAD_REQUEST, true));
+    }
+
+    #[test]
+    fn picks_verified_contract_by_address() {
+        let item = |address: &str| ResultItem {
+            address: address.to_string(),
+            status: "perfect".to_string(),
+            storage_timestamp: None,
+        };
+        let result = vec![item("0x1111"), item("0xABCD")];
+        let picked = verified_contract(result, "0xabcd").expect("contract is in the result");
+        assert_eq!(picked.address, "0xABCD");
+
+        let error = verified_contract(vec![item("0x1111")], "0xabcd")
+            .err()
+            .expect("contract is not in the result");
+        assert_eq!(error.code, ErrorCode::SourcifyInvalidResponse);
+    }
+}
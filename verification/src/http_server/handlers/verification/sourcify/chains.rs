@@ -0,0 +1,125 @@
+use super::api::SourcifyApi;
+use crate::http_server::error::{ApiError, ErrorCode};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Time a failed fetch of the supported chains is cached for,
+/// so that requests don't wait for the unavailable Sourcify one after another.
+const FAILURE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Resolves chains specified in requests into Sourcify chain ids
+/// and checks that Sourcify supports them.
+pub struct Chains {
+    /// Chain ids by the names of the networks
+    names: BTreeMap<String, u64>,
+    cache_ttl: Duration,
+    /// Ids of the supported chains and the time they were fetched at
+    supported: parking_lot::Mutex<Option<(Instant, Arc<HashSet<u64>>)>>,
+    /// Time of the last failed fetch
+    failed_at: parking_lot::Mutex<Option<Instant>>,
+    /// Held while the list is fetched, so that concurrent requests wait for the single fetch
+    fetching: tokio::sync::Mutex<()>,
+}
+
+impl Chains {
+    pub fn new(names: BTreeMap<String, u64>, cache_ttl: Duration) -> Self {
+        Self {
+            names,
+            cache_ttl,
+            supported: Default::default(),
+            failed_at: Default::default(),
+            fetching: Default::default(),
+        }
+    }
+
+    /// Returns the id of the chain specified either by its id or by its name.
+    ///
+    /// If the list of chains supported by Sourcify is unavailable, the chain is not checked
+    /// and Sourcify validates it itself.
+    pub(super) async fn resolve(
+        &self,
+        sourcify_client: &impl SourcifyApi,
+        chain: &str,
+    ) -> Result<String, ApiError> {
+        let chain_id = match (chain.parse::<u64>(), self.names.get(chain)) {
+            (Ok(chain_id), _) | (_, Some(&chain_id)) => chain_id,
+            _ => {
+                let names: Vec<_> = self.names.keys().map(String::as_str).collect();
+                return Err(ApiError::new(
+                    ErrorCode::UnsupportedChain,
+                    format!(
+                        "unknown chain '{}', specify its id or one of the names: {}",
+                        chain,
+                        names.join(", ")
+                    ),
+                ));
+            }
+        };
+        if let Some(supported) = self.supported(sourcify_client).await {
+            if !supported.contains(&chain_id) {
+                return Err(ApiError::new(
+                    ErrorCode::UnsupportedChain,
+                    format!("chain {} is not supported by Sourcify", chain_id),
+                ));
+            }
+        }
+        Ok(chain_id.to_string())
+    }
+
+    async fn supported(&self, sourcify_client: &impl SourcifyApi) -> Option<Arc<HashSet<u64>>> {
+        if let Some(supported) = self.cached() {
+            return Some(supported);
+        }
+        let _fetching = self.fetching.lock().await;
+        // the list could be fetched while waiting for the lock
+        if let Some(supported) = self.cached() {
+            return Some(supported);
+        }
+        let recently_failed = self
+            .failed_at
+            .lock()
+            .is_some_and(|failed_at| failed_at.elapsed() < FAILURE_CACHE_TTL.min(self.cache_ttl));
+        if recently_failed {
+            return self.outdated();
+        }
+        match sourcify_client.chains_request().await {
+            Ok(chains) => {
+                let supported: Arc<HashSet<_>> = Arc::new(
+                    chains
+                        .into_iter()
+                        .filter(|chain| chain.supported)
+                        .map(|chain| chain.chain_id)
+                        .collect(),
+                );
+                *self.supported.lock() = Some((Instant::now(), supported.clone()));
+                *self.failed_at.lock() = None;
+                Some(supported)
+            }
+            Err(err) => {
+                log::warn!("couldn't fetch the chains supported by Sourcify: {}", err);
+                *self.failed_at.lock() = Some(Instant::now());
+                self.outdated()
+            }
+        }
+    }
+
+    /// Returns the list if it has been fetched within the cache ttl.
+    fn cached(&self) -> Option<Arc<HashSet<u64>>> {
+        self.supported
+            .lock()
+            .as_ref()
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.cache_ttl)
+            .map(|(_, supported)| supported.clone())
+    }
+
+    /// Returns the last fetched list, as the outdated list is still better than nothing.
+    fn outdated(&self) -> Option<Arc<HashSet<u64>>> {
+        self.supported
+            .lock()
+            .as_ref()
+            .map(|(_, supported)| supported.clone())
+    }
+}
@@ -0,0 +1,318 @@
+//! Conversion of verification results into Sourcify repository layout
+//! (https://docs.sourcify.dev/docs/repository-structure/), the opposite of
+//! building the results from the files stored by Sourcify.
+
+use super::metadata::{Compiler, Metadata, MetadataSettings, MetadataSource, Optimizer, Output};
+use crate::{MatchType, VerificationResult};
+use ethers_core::{
+    types::{Address, H256},
+    utils::{keccak256, to_checksum},
+};
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("invalid contract address {0}")]
+    Address(String),
+    #[error("invalid abi: {0}")]
+    Abi(serde_json::Error),
+    #[error("source file path {0} points outside of the sources folder")]
+    SourcePath(String),
+    #[error("invalid metadata: {0}")]
+    Metadata(String),
+}
+
+/// Returns the files of the verified contract by their paths relative to the repository root:
+/// `contracts/<full_match|partial_match>/<chain>/<address>/` containing `metadata.json`,
+/// `sources/` and `constructor-args.txt` if the contract has constructor arguments.
+///
+/// The original metadata (e.g. of Sourcify imports or of the metadata route) is written
+/// byte-for-byte, and only then perfect matches are stored as full matches. Otherwise
+/// the metadata is rebuilt from the result; its hash can't match the one in the bytecode,
+/// so the contract is always stored as a partial match.
+pub fn repository_files(
+    result: &VerificationResult,
+    original_metadata: Option<&str>,
+    chain_id: u64,
+    address: &str,
+) -> Result<BTreeMap<PathBuf, String>, ExportError> {
+    let address =
+        Address::from_str(address).map_err(|_| ExportError::Address(address.to_string()))?;
+    let metadata = match original_metadata {
+        Some(metadata) => {
+            check_metadata(result, metadata)?;
+            metadata.to_string()
+        }
+        None => serde_json::to_string(&metadata(result)?).expect("metadata should be serializable"),
+    };
+    let match_folder = match (result.match_type, original_metadata) {
+        (Some(MatchType::Perfect), Some(_)) => "full_match",
+        _ => "partial_match",
+    };
+    let contract_folder = Path::new("contracts")
+        .join(match_folder)
+        .join(chain_id.to_string())
+        .join(to_checksum(&address, None));
+
+    let mut files = BTreeMap::new();
+    for (name, content) in &result.sources {
+        let path = source_path(name)?;
+        files.insert(contract_folder.join("sources").join(path), content.clone());
+    }
+    files.insert(contract_folder.join("metadata.json"), metadata);
+    if let Some(args) = &result.constructor_arguments {
+        files.insert(
+            contract_folder.join("constructor-args.txt"),
+            args.to_string(),
+        );
+    }
+    Ok(files)
+}
+
+/// The original metadata must describe the verified contract: its compilation target,
+/// compiler version and sources must be the ones of the result.
+fn check_metadata(result: &VerificationResult, metadata: &str) -> Result<(), ExportError> {
+    let metadata: Metadata =
+        serde_json::from_str(metadata).map_err(|err| ExportError::Metadata(err.to_string()))?;
+    let target = metadata.settings.compilation_target;
+    if target.get(&result.file_name) != Some(&result.contract_name) {
+        return Err(ExportError::Metadata(format!(
+            "compilation target {:?} is not {}:{}",
+            target, result.file_name, result.contract_name
+        )));
+    }
+    let compiler_version = result.compiler_version.trim_start_matches('v');
+    if metadata.compiler.version.trim_start_matches('v') != compiler_version {
+        return Err(ExportError::Metadata(format!(
+            "compiler version {} is not {}",
+            metadata.compiler.version, compiler_version
+        )));
+    }
+    let mismatched: Vec<_> = metadata
+        .sources
+        .iter()
+        .filter(|(name, source)| {
+            result
+                .sources
+                .get(*name)
+                .map(|content| H256::from(keccak256(content)))
+                != Some(source.keccak256)
+        })
+        .map(|(name, _)| name.as_str())
+        .chain(
+            result
+                .sources
+                .keys()
+                .filter(|name| !metadata.sources.contains_key(*name))
+                .map(String::as_str),
+        )
+        .collect();
+    if !mismatched.is_empty() {
+        return Err(ExportError::Metadata(format!(
+            "sources don't match the result: {}",
+            mismatched.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+fn metadata(result: &VerificationResult) -> Result<Metadata, ExportError> {
+    let sources = result
+        .sources
+        .iter()
+        .map(|(name, content)| {
+            let source = MetadataSource {
+                keccak256: H256::from(keccak256(content)),
+                urls: vec![],
+                content: None,
+                license: None,
+            };
+            (name.clone(), source)
+        })
+        .collect();
+    let evm_version = (result.evm_version != "default").then(|| result.evm_version.clone());
+    Ok(Metadata {
+        version: Some(1),
+        language: Some("Solidity".to_string()),
+        compiler: Compiler {
+            version: result.compiler_version.trim_start_matches('v').to_string(),
+            keccak256: None,
+        },
+        sources,
+        settings: MetadataSettings {
+            compilation_target: BTreeMap::from([(
+                result.file_name.clone(),
+                result.contract_name.clone(),
+            )]),
+            evm_version,
+            libraries: result.contract_libraries.clone(),
+            optimizer: Optimizer {
+                enabled: result.optimization,
+                runs: result.optimization_runs,
+                details: None,
+            },
+            metadata: None,
+            remappings: vec![],
+            via_ir: None,
+        },
+        output: Output {
+            abi: serde_json::from_str(&result.abi).map_err(ExportError::Abi)?,
+            devdoc: None,
+            userdoc: None,
+        },
+    })
+}
+
+/// Sources are stored by their names in the metadata, which must stay inside the folder.
+fn source_path(name: &str) -> Result<PathBuf, ExportError> {
+    let path = Path::new(name);
+    let is_inside = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if is_inside {
+        Ok(path.to_path_buf())
+    } else {
+        Err(ExportError::SourcePath(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::types::Files, *};
+
+    fn result() -> VerificationResult {
+        VerificationResult {
+            file_name: "contracts/A.sol".into(),
+            contract_name: "A".into(),
+            compiler_version: "v0.8.14+commit.80d49f37".into(),
+            evm_version: "london".into(),
+            constructor_arguments: None,
+            optimization: Some(true),
+            optimization_runs: Some(200),
+            contract_libraries: BTreeMap::new(),
+            abi: "[]".into(),
+            sources: BTreeMap::from([
+                ("contracts/A.sol".into(), "contract A {}".into()),
+                ("contracts/B.sol".into(), "contract B {}".into()),
+            ]),
+            match_type: None,
+            storage_timestamp: None,
+        }
+    }
+
+    #[test]
+    fn export_in_repository_layout() {
+        let address = "0xcafecafecafecafecafecafecafecafecafecafe";
+        let files = repository_files(&result(), None, 100, address).unwrap();
+        let folder = "contracts/partial_match/100/0xCAfEcAfeCAfECaFeCaFecaFecaFECafECafeCaFe";
+        let paths: Vec<_> = files.keys().map(|path| path.to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            [
+                format!("{}/metadata.json", folder),
+                format!("{}/sources/contracts/A.sol", folder),
+                format!("{}/sources/contracts/B.sol", folder),
+            ]
+        );
+
+        // the exported metadata is parsed back into the same result
+        let files = Files(
+            files
+                .into_iter()
+                .map(|(path, content)| {
+                    let name = path.strip_prefix(folder).unwrap();
+                    let name = name.strip_prefix("sources").unwrap_or(name);
+                    (name.to_string_lossy().to_string(), content)
+                })
+                .collect(),
+        );
+        let mut expected = result();
+        expected.compiler_version = "0.8.14+commit.80d49f37".into();
+        assert_eq!(VerificationResult::try_from(files).unwrap(), expected);
+
+        let mut result = result();
+        result.sources.insert("../A.sol".into(), "".into());
+        assert!(matches!(
+            repository_files(&result, None, 100, address),
+            Err(ExportError::SourcePath(_))
+        ));
+    }
+
+    #[test]
+    fn keeps_original_metadata() {
+        let address = "0xcafecafecafecafecafecafecafecafecafecafe";
+        let mut result = result();
+        result.match_type = Some(MatchType::Perfect);
+        let folder = "contracts/full_match/100/0xCAfEcAfeCAfECaFeCaFecaFecaFECafECafeCaFe";
+
+        // rebuilt metadata can't be a full match
+        let files = repository_files(&result, None, 100, address).unwrap();
+        assert!(files
+            .keys()
+            .all(|path| path.starts_with("contracts/partial_match")));
+
+        let original = serde_json::to_string_pretty(&metadata(&result).unwrap()).unwrap();
+        let files = repository_files(&result, Some(&original), 100, address).unwrap();
+        let path = PathBuf::from(format!("{}/metadata.json", folder));
+        assert_eq!(files.get(&path), Some(&original));
+
+        result.contract_name = "B".into();
+        assert!(matches!(
+            repository_files(&result, Some(&original), 100, address),
+            Err(ExportError::Metadata(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_metadata_of_other_compiler() {
+        let address = "0xcafecafecafecafecafecafecafecafecafecafe";
+        let original = serde_json::to_string(&metadata(&result()).unwrap()).unwrap();
+
+        let mut result = result();
+        result.compiler_version = "v0.8.15+commit.e14f2714".into();
+        let error = repository_files(&result, Some(&original), 100, address).unwrap_err();
+        assert!(
+            matches!(&error, ExportError::Metadata(message) if message.contains("compiler version")),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn rejects_metadata_of_other_sources() {
+        let address = "0xcafecafecafecafecafecafecafecafecafecafe";
+        let original = serde_json::to_string(&metadata(&result()).unwrap()).unwrap();
+
+        // changed source
+        let mut changed = result();
+        changed
+            .sources
+            .insert("contracts/B.sol".into(), "contract B { }".into());
+        // source missing from the metadata
+        let mut extra = result();
+        extra
+            .sources
+            .insert("contracts/C.sol".into(), "contract C {}".into());
+        // source missing from the result
+        let mut missing = result();
+        missing.sources.remove("contracts/B.sol");
+
+        for (result, source) in [
+            (changed, "contracts/B.sol"),
+            (extra, "contracts/C.sol"),
+            (missing, "contracts/B.sol"),
+        ] {
+            let error = repository_files(&result, Some(&original), 100, address).unwrap_err();
+            assert!(
+                matches!(&error, ExportError::Metadata(message) if message.ends_with(source)),
+                "{}",
+                error
+            );
+        }
+    }
+}
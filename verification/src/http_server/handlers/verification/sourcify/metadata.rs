@@ -1,7 +1,14 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
 
-use ethers_solc::EvmVersion;
-use serde::Deserialize;
+use ethers_core::{types::H256, utils::keccak256};
+use ethers_solc::{
+    artifacts::{self, BytecodeHash, Libraries, OptimizerDetails, Settings, Source},
+    remappings::Remapping,
+    CompilerInput, EvmVersion,
+};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use thiserror::Error;
 
 use crate::VerificationResult;
 
@@ -9,52 +16,125 @@ use super::types::Files;
 
 const METADATA_FILE_NAME: &str = "metadata.json";
 
+/// Contract metadata generated by the compiler
+/// (https://docs.soliditylang.org/en/v0.8.15/metadata.html).
 // There is struct for metadata in ethers_solc::artifacts::Metadata
 // however it is for standard json input of compiler and
 // has different `libraries` field structure
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Metadata {
-    pub settings: MetadataSettings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
     pub compiler: Compiler,
+    pub sources: BTreeMap<String, MetadataSource>,
+    pub settings: MetadataSettings,
     pub output: Output,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct Compiler {
+    pub version: String,
+    /// Hash of the compiler binary, present only for custom builds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keccak256: Option<H256>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct MetadataSource {
+    pub keccak256: H256,
+    /// Urls the source file may be retrieved from (e.g. `bzz-raw://` or `dweb:/ipfs/`)
+    #[serde(default)]
+    pub urls: Vec<String>,
+    /// Literal content of the source file, used instead of `urls`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MetadataSettings {
+    /// Source file and name of the contract the metadata was generated for
     pub compilation_target: BTreeMap<String, String>,
-    pub optimizer: Optimizer,
+    /// Kept as a string, so that versions unknown to `ethers_solc` don't fail the parsing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evm_version: Option<String>,
+    #[serde(default)]
     pub libraries: BTreeMap<String, String>,
-    #[serde(rename = "camelCase")]
-    pub evm_version: Option<EvmVersion>,
+    pub optimizer: Optimizer,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<SettingsMetadata>,
+    #[serde(default)]
+    pub remappings: Vec<String>,
+    #[serde(default, rename = "viaIR", skip_serializing_if = "Option::is_none")]
+    pub via_ir: Option<bool>,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Optimizer {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub runs: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<OptimizerDetails>,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
-pub struct Compiler {
-    pub version: String,
+#[serde_as]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_literal_content: Option<bool>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytecode_hash: Option<BytecodeHash>,
+    #[serde(
+        default,
+        rename = "appendCBOR",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub append_cbor: Option<bool>,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Output {
     pub abi: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub devdoc: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub userdoc: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum MetadataError {
+    #[error("file {} not found", METADATA_FILE_NAME)]
+    NotFound,
+    #[error("invalid {}: {0}", METADATA_FILE_NAME)]
+    Parse(String),
+    #[error("compilation target not found")]
+    NoCompilationTarget,
+    #[error("source files don't match keccak256 hashes from metadata: {}", .0.join(", "))]
+    SourcesMismatch(Vec<String>),
+    #[error("source files from metadata are missing: {}", .0.join(", "))]
+    SourcesMissing(Vec<String>),
+    #[error("invalid compiler settings in metadata: {0}")]
+    InvalidSettings(String),
 }
 
 impl Files {
-    fn extract_metadata_and_source_files(
+    pub(crate) fn extract_metadata_and_source_files(
         self,
-    ) -> Result<(Metadata, BTreeMap<String, String>), anyhow::Error> {
+    ) -> Result<(Metadata, BTreeMap<String, String>), MetadataError> {
         let metadata_content = self
             .0
             .get(METADATA_FILE_NAME)
-            .ok_or_else(|| anyhow::Error::msg(format!("file {} not found", METADATA_FILE_NAME)))?;
-        let metadata: Metadata =
-            serde_json::from_str(metadata_content).map_err(anyhow::Error::msg)?;
+            .ok_or(MetadataError::NotFound)?;
+        let metadata: Metadata = serde_json::from_str(metadata_content)
+            .map_err(|err| MetadataError::Parse(err.to_string()))?;
         let source_files: BTreeMap<String, String> = self
             .0
             .into_iter()
@@ -65,11 +145,184 @@ impl Files {
     }
 }
 
+impl Metadata {
+    /// Checks the source files against their keccak256 hashes from the metadata
+    /// and returns the sources by their names in the metadata.
+    ///
+    /// The files may be named either the same as in the metadata or by the last
+    /// components of the path (e.g. `Token.sol` for `contracts/Token.sol`),
+    /// as long as the name is not ambiguous. Sources with literal content
+    /// in the metadata may be omitted.
+    pub fn validate_sources(
+        &self,
+        files: BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>, MetadataError> {
+        let mut sources = BTreeMap::new();
+        let mut mismatched = vec![];
+        for (name, content) in files {
+            let source = self.source_name(&name).and_then(|source_name| {
+                let source = &self.sources[source_name];
+                (H256::from(keccak256(&content)) == source.keccak256).then_some(source_name)
+            });
+            match source {
+                Some(source_name) => {
+                    sources.insert(source_name.clone(), content);
+                }
+                None => mismatched.push(name),
+            }
+        }
+        if !mismatched.is_empty() {
+            return Err(MetadataError::SourcesMismatch(mismatched));
+        }
+
+        let mut missing = vec![];
+        for (name, source) in &self.sources {
+            if sources.contains_key(name) {
+                continue;
+            }
+            match &source.content {
+                Some(content) => {
+                    sources.insert(name.clone(), content.clone());
+                }
+                None => missing.push(name.clone()),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(MetadataError::SourcesMissing(missing));
+        }
+        Ok(sources)
+    }
+
+    /// Picks the sources of the metadata out of the files stored by Sourcify
+    /// and checks them against their keccak256 hashes.
+    ///
+    /// Unlike [`Metadata::validate_sources`], the files which are not sources of the metadata
+    /// (e.g. `constructor-args.txt` or `library-map.json`) are skipped. The files may be named
+    /// by the last components of the paths, so several of them may correspond to a source,
+    /// which then matches if any of them has the expected hash.
+    pub fn stored_sources(
+        &self,
+        files: BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>, MetadataError> {
+        let mut sources = BTreeMap::new();
+        let mut mismatched = vec![];
+        let mut missing = vec![];
+        for (name, source) in &self.sources {
+            let candidates: Vec<_> = files
+                .iter()
+                .filter(|(file_name, _)| is_file_of_source(name, file_name))
+                .collect();
+            let matched = candidates
+                .iter()
+                .find(|(_, content)| H256::from(keccak256(content)) == source.keccak256);
+            match (matched, &source.content) {
+                (Some((_, content)), _) => {
+                    sources.insert(name.clone(), content.to_string());
+                }
+                (None, _) if !candidates.is_empty() => mismatched.extend(
+                    candidates
+                        .into_iter()
+                        .map(|(file_name, _)| file_name.clone()),
+                ),
+                (None, Some(content)) => {
+                    sources.insert(name.clone(), content.clone());
+                }
+                (None, None) => missing.push(name.clone()),
+            }
+        }
+        if !mismatched.is_empty() {
+            return Err(MetadataError::SourcesMismatch(mismatched));
+        }
+        if !missing.is_empty() {
+            return Err(MetadataError::SourcesMissing(missing));
+        }
+        Ok(sources)
+    }
+
+    /// Reconstructs the compiler input the metadata was generated from.
+    /// `sources` are expected to be validated with [`Metadata::validate_sources`].
+    pub fn compiler_input(
+        self,
+        sources: BTreeMap<String, String>,
+    ) -> Result<CompilerInput, MetadataError> {
+        let settings = self.settings;
+        let invalid = |err: String| MetadataError::InvalidSettings(err);
+
+        let mut compiler_settings = Settings::default();
+        compiler_settings.optimizer.enabled = settings.optimizer.enabled;
+        compiler_settings.optimizer.runs = settings.optimizer.runs;
+        compiler_settings.optimizer.details = settings.optimizer.details;
+        compiler_settings.evm_version = settings
+            .evm_version
+            .as_deref()
+            .map(EvmVersion::from_str)
+            .transpose()
+            .map_err(invalid)?;
+        compiler_settings.remappings = settings
+            .remappings
+            .iter()
+            .map(|remapping| Remapping::from_str(remapping))
+            .collect::<Result<_, _>>()
+            .map_err(|err| invalid(err.to_string()))?;
+        compiler_settings.metadata =
+            settings
+                .metadata
+                .map(|metadata| artifacts::SettingsMetadata {
+                    use_literal_content: metadata.use_literal_content,
+                    bytecode_hash: metadata.bytecode_hash,
+                });
+        compiler_settings.via_ir = settings.via_ir;
+        compiler_settings.libraries = libraries(settings.libraries);
+
+        Ok(CompilerInput {
+            language: self.language.unwrap_or_else(|| "Solidity".to_string()),
+            sources: sources
+                .into_iter()
+                .map(|(name, content)| (PathBuf::from(name), Source { content }))
+                .collect(),
+            settings: compiler_settings,
+        })
+    }
+
+    /// Name of the metadata source the file corresponds to.
+    fn source_name(&self, file_name: &str) -> Option<&String> {
+        if let Some((name, _)) = self.sources.get_key_value(file_name) {
+            return Some(name);
+        }
+        let suffix = format!("/{}", file_name.trim_start_matches('/'));
+        let mut candidates = self.sources.keys().filter(|name| name.ends_with(&suffix));
+        match (candidates.next(), candidates.next()) {
+            (Some(name), None) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// Whether the file is named either the same as the source or by the last components of its path.
+fn is_file_of_source(source_name: &str, file_name: &str) -> bool {
+    let file_name = file_name.trim_start_matches('/');
+    source_name == file_name || source_name.ends_with(&format!("/{}", file_name))
+}
+
+/// Converts libraries from the metadata format (`<file>:<name>` or just `<name>` for
+/// older compilers) into the format of the compiler input.
+fn libraries(libraries: BTreeMap<String, String>) -> Libraries {
+    let mut libs: BTreeMap<PathBuf, BTreeMap<String, String>> = BTreeMap::new();
+    for (name, address) in libraries {
+        let (file, name) = name.rsplit_once(':').unwrap_or(("", &name));
+        libs.entry(PathBuf::from(file))
+            .or_default()
+            .insert(name.to_string(), address);
+    }
+    Libraries { libs }
+}
+
 impl TryFrom<Files> for VerificationResult {
-    type Error = anyhow::Error;
+    type Error = MetadataError;
 
     fn try_from(files: Files) -> Result<Self, Self::Error> {
-        let (metadata, source_files) = files.extract_metadata_and_source_files()?;
+        let (metadata, files) = files.extract_metadata_and_source_files()?;
+        let sources = metadata.stored_sources(files)?;
 
         let compiler_version = metadata.compiler.version;
         let (file_name, contract_name) = metadata
@@ -77,16 +330,16 @@ impl TryFrom<Files> for VerificationResult {
             .compilation_target
             .into_iter()
             .next()
-            .ok_or_else(|| anyhow::Error::msg("compilation target not found"))?;
+            .ok_or(MetadataError::NoCompilationTarget)?;
         let evm_version = metadata
             .settings
             .evm_version
-            .unwrap_or_default()
-            .to_string();
+            .unwrap_or_else(|| "default".to_string());
         let optimization = metadata.settings.optimizer.enabled;
         let optimization_runs = metadata.settings.optimizer.runs;
         let contract_libraries: BTreeMap<String, String> = metadata.settings.libraries;
-        let abi = serde_json::to_string(&metadata.output.abi)?;
+        let abi = serde_json::to_string(&metadata.output.abi)
+            .expect("Is result of parsing and, thus, should be always valid");
 
         Ok(VerificationResult {
             file_name,
@@ -99,7 +352,9 @@ impl TryFrom<Files> for VerificationResult {
             optimization,
             optimization_runs,
             abi,
-            sources: source_files,
+            sources,
+            match_type: None,
+            storage_timestamp: None,
         })
     }
 }
@@ -112,24 +367,288 @@ mod tests {
         "compiler": {
             "version": "0.8.14+commit.80d49f37"
         },
+        "language": "Solidity",
         "output": {
-            "abi": [{"inputs":[],"name":"retrieve","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"}]
+            "abi": [{"inputs":[],"name":"retrieve","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"}],
+            "devdoc": {"kind": "dev", "methods": {}, "version": 1},
+            "userdoc": {"kind": "user", "methods": {}, "version": 1}
         },
         "settings": {
             "compilationTarget": {
-                "example.sol": "Example"
+                "contracts/example.sol": "Example"
             },
             "evmVersion": "london",
             "libraries": {
                 "SafeMath": "0xFBe36e5cAD207d5fDee40E6568bb276a351f6713"
             },
+            "metadata": {
+                "bytecodeHash": "ipfs"
+            },
             "optimizer": {
                 "enabled": false,
                 "runs": 200
+            },
+            "remappings": [
+                "@openzeppelin/=node_modules/@openzeppelin/"
+            ],
+            "viaIR": true
+        },
+        "sources": {
+            "contracts/example.sol": {
+                "keccak256": "0x2bbf5c2f6f8e4a4ac1e8ebf8d3a4a7f1d4ab4e5d05cb2dd6f2bd4ab8a6e9c5b4",
+                "license": "MIT",
+                "urls": [
+                    "bzz-raw://7d6b2f4e3e4b5e2c9b8b4b3fbd6d0e1a2c5f7e8d9a0b1c2d3e4f5a6b7c8d9e0f",
+                    "dweb:/ipfs/QmQ8jTHhHBv6a4bFfmMNmUk1jRyhRsA4ZPPwZ4XjR3FdkM"
+                ]
+            },
+            "contracts/Lib.sol": {
+                "keccak256": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "content": "library Lib {}"
             }
-        }
+        },
+        "version": 1
     }"#;
 
+    /// Metadata with the hash of the `example.sol` source set to the hash of `content`.
+    fn metadata_json(content: &str) -> String {
+        let hash = |content| format!("{:?}", H256::from(keccak256(content)));
+        DEFAULT_METADATA
+            .replace(
+                "0x2bbf5c2f6f8e4a4ac1e8ebf8d3a4a7f1d4ab4e5d05cb2dd6f2bd4ab8a6e9c5b4",
+                &hash(content),
+            )
+            .replace(
+                "0x0000000000000000000000000000000000000000000000000000000000000000",
+                &hash("library Lib {}"),
+            )
+    }
+
+    fn metadata() -> Metadata {
+        serde_json::from_str(&metadata_json("content")).expect("metadata should be valid")
+    }
+
+    fn files(files: &[(&str, &str)]) -> BTreeMap<String, String> {
+        files
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_full_metadata() {
+        let metadata = metadata();
+        assert_eq!(metadata.version, Some(1));
+        assert_eq!(metadata.language.as_deref(), Some("Solidity"));
+        assert_eq!(metadata.compiler.keccak256, None);
+        assert_eq!(metadata.settings.evm_version.as_deref(), Some("london"));
+        assert_eq!(
+            metadata.settings.remappings,
+            vec!["@openzeppelin/=node_modules/@openzeppelin/"]
+        );
+        assert_eq!(metadata.settings.via_ir, Some(true));
+        assert_eq!(
+            metadata.settings.metadata.and_then(|m| m.bytecode_hash),
+            Some(BytecodeHash::Ipfs)
+        );
+        assert!(metadata.output.devdoc.is_some());
+
+        let source = &metadata.sources["contracts/example.sol"];
+        assert_eq!(source.keccak256, H256::from(keccak256("content")));
+        assert_eq!(source.license.as_deref(), Some("MIT"));
+        assert_eq!(source.urls.len(), 2);
+        let lib = &metadata.sources["contracts/Lib.sol"];
+        assert_eq!(lib.content.as_deref(), Some("library Lib {}"));
+
+        // only fields required for verification are mandatory
+        let minimal: Metadata = serde_json::from_str(
+            r#"{
+                "compiler": {"version": "0.4.13+commit.0fb4cb1a"},
+                "sources": {},
+                "settings": {
+                    "compilationTarget": {"example.sol": "Example"},
+                    "optimizer": {"enabled": true, "runs": 200}
+                },
+                "output": {"abi": []}
+            }"#,
+        )
+        .expect("minimal metadata should be valid");
+        assert_eq!(minimal.settings.evm_version, None);
+        assert_eq!(minimal.settings.via_ir, None);
+    }
+
+    #[test]
+    fn validate_sources() {
+        let metadata = metadata();
+        let expected = files(&[
+            ("contracts/example.sol", "content"),
+            ("contracts/Lib.sol", "library Lib {}"),
+        ]);
+
+        for (input, result) in [
+            // files named the same as in metadata
+            (
+                files(&[("contracts/example.sol", "content")]),
+                Ok(expected.clone()),
+            ),
+            // files named by the last components of the path
+            (files(&[("example.sol", "content")]), Ok(expected.clone())),
+            (
+                files(&[("example.sol", "content"), ("Lib.sol", "library Lib {}")]),
+                Ok(expected.clone()),
+            ),
+            (
+                files(&[("example.sol", "modified")]),
+                Err(MetadataError::SourcesMismatch(vec!["example.sol".into()])),
+            ),
+            (
+                files(&[("example.sol", "content"), ("unknown.sol", "content")]),
+                Err(MetadataError::SourcesMismatch(vec!["unknown.sol".into()])),
+            ),
+            (
+                files(&[]),
+                Err(MetadataError::SourcesMissing(vec![
+                    "contracts/example.sol".into()
+                ])),
+            ),
+        ] {
+            assert_eq!(
+                metadata.validate_sources(input.clone()),
+                result,
+                "{:?}",
+                input
+            );
+        }
+
+        // ambiguous names are not matched
+        let mut metadata = metadata;
+        metadata.sources.insert(
+            "other/example.sol".into(),
+            MetadataSource {
+                keccak256: H256::from(keccak256("content")),
+                urls: vec![],
+                content: Some("content".into()),
+                license: None,
+            },
+        );
+        assert_eq!(
+            metadata.validate_sources(files(&[("example.sol", "content")])),
+            Err(MetadataError::SourcesMismatch(vec!["example.sol".into()]))
+        );
+    }
+
+    #[test]
+    fn stored_sources() {
+        let mut metadata = metadata();
+        let expected = files(&[
+            ("contracts/example.sol", "content"),
+            ("contracts/Lib.sol", "library Lib {}"),
+        ]);
+
+        // other files of the repository are skipped
+        let stored = files(&[
+            ("example.sol", "content"),
+            ("constructor-args.txt", "0xcafe"),
+            ("library-map.json", "{}"),
+            ("immutable-references.json", "{}"),
+            ("creator-tx-hash.txt", "0x01"),
+        ]);
+        assert_eq!(metadata.stored_sources(stored), Ok(expected.clone()));
+        assert_eq!(
+            metadata.stored_sources(files(&[("example.sol", "modified")])),
+            Err(MetadataError::SourcesMismatch(vec!["example.sol".into()]))
+        );
+        assert_eq!(
+            metadata.stored_sources(files(&[("README.md", "content")])),
+            Err(MetadataError::SourcesMissing(vec![
+                "contracts/example.sol".into()
+            ]))
+        );
+
+        // sources with the same last components of the paths
+        metadata.sources.insert(
+            "other/example.sol".into(),
+            MetadataSource {
+                keccak256: H256::from(keccak256("other")),
+                urls: vec![],
+                content: None,
+                license: None,
+            },
+        );
+        let stored = files(&[
+            ("contracts/example.sol", "content"),
+            ("other/example.sol", "other"),
+        ]);
+        let mut expected = expected;
+        expected.insert("other/example.sol".into(), "other".into());
+        assert_eq!(metadata.stored_sources(stored), Ok(expected));
+    }
+
+    #[test]
+    fn reconstruct_compiler_input() {
+        let metadata = metadata();
+        let sources = metadata
+            .validate_sources(files(&[("example.sol", "content")]))
+            .unwrap();
+        let input = metadata.compiler_input(sources).unwrap();
+
+        assert_eq!(input.language, "Solidity");
+        assert_eq!(
+            input.sources.keys().collect::<Vec<_>>(),
+            vec![
+                &PathBuf::from("contracts/Lib.sol"),
+                &PathBuf::from("contracts/example.sol")
+            ]
+        );
+        let settings = input.settings;
+        assert_eq!(settings.evm_version, Some(EvmVersion::London));
+        assert_eq!(settings.optimizer.enabled, Some(false));
+        assert_eq!(settings.optimizer.runs, Some(200));
+        assert_eq!(settings.via_ir, Some(true));
+        assert_eq!(
+            settings.remappings,
+            vec![Remapping::from_str("@openzeppelin/=node_modules/@openzeppelin/").unwrap()]
+        );
+        assert_eq!(
+            settings
+                .metadata
+                .and_then(|metadata| metadata.bytecode_hash),
+            Some(BytecodeHash::Ipfs)
+        );
+        assert_eq!(
+            settings.libraries.libs,
+            BTreeMap::from([(
+                PathBuf::from(""),
+                BTreeMap::from([(
+                    "SafeMath".to_string(),
+                    "0xFBe36e5cAD207d5fDee40E6568bb276a351f6713".to_string()
+                )])
+            )])
+        );
+
+        let libs = libraries(BTreeMap::from([
+            ("contracts/Lib.sol:Lib".to_string(), "0x01".to_string()),
+            ("contracts/Lib.sol:Other".to_string(), "0x02".to_string()),
+        ]));
+        assert_eq!(
+            libs.libs,
+            BTreeMap::from([(
+                PathBuf::from("contracts/Lib.sol"),
+                BTreeMap::from([
+                    ("Lib".to_string(), "0x01".to_string()),
+                    ("Other".to_string(), "0x02".to_string())
+                ])
+            )])
+        );
+
+        let mut metadata = self::metadata();
+        metadata.settings.evm_version = Some("unknown".into());
+        assert!(matches!(
+            metadata.compiler_input(BTreeMap::new()),
+            Err(MetadataError::InvalidSettings(_))
+        ));
+    }
+
     #[test]
     fn parse_metadata_from_files() {
         let files = Files(BTreeMap::from([
@@ -145,16 +664,17 @@ mod tests {
         );
 
         let files = Files(BTreeMap::from([("source.sol".into(), "content".into())]));
-        files
-            .extract_metadata_and_source_files()
-            .expect_err("Parsing files without metadata should fail");
+        assert_eq!(
+            files.extract_metadata_and_source_files().unwrap_err(),
+            MetadataError::NotFound
+        );
     }
 
     #[test]
     fn parse_response_from_files() {
         let files = Files(BTreeMap::from([
-            ("source.sol".into(), "content".into()),
-            (METADATA_FILE_NAME.into(), DEFAULT_METADATA.into()),
+            ("example.sol".into(), "content".into()),
+            (METADATA_FILE_NAME.into(), metadata_json("content")),
         ]));
 
         let verification_result =
@@ -162,7 +682,7 @@ mod tests {
         assert_eq!(
             verification_result,
             VerificationResult {
-                file_name: "example.sol".into(),
+                file_name: "contracts/example.sol".into(),
                 contract_name: "Example".into(),
                 compiler_version: "0.8.14+commit.80d49f37".into(),
                 evm_version: "london".into(),
@@ -171,10 +691,24 @@ mod tests {
                 optimization: Some(false),
                 optimization_runs: Some(200),
                 abi: r#"[{"inputs":[],"name":"retrieve","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"}]"#.into(),
-                sources: BTreeMap::from([("source.sol".into(), "content".into())]),
+                sources: BTreeMap::from([
+                    ("contracts/example.sol".into(), "content".into()),
+                    ("contracts/Lib.sol".into(), "library Lib {}".into()),
+                ]),
+                match_type: None,
+                storage_timestamp: None,
             }
         );
 
+        let files = Files(BTreeMap::from([
+            ("example.sol".into(), "modified".into()),
+            (METADATA_FILE_NAME.into(), metadata_json("content")),
+        ]));
+        assert_eq!(
+            VerificationResult::try_from(files).unwrap_err(),
+            MetadataError::SourcesMismatch(vec!["example.sol".into()])
+        );
+
         let files = Files(BTreeMap::from([("source.sol".into(), "content".into())]));
         VerificationResult::try_from(files)
             .expect_err("Parsing files without metadata should fail");
@@ -1,20 +1,92 @@
 mod api;
-mod metadata;
-mod types;
+mod chains;
+pub(crate) mod export;
+pub(crate) mod metadata;
+pub(crate) mod session;
+pub(crate) mod types;
 
-pub use self::api::SourcifyApiClient;
+pub use self::{
+    api::{RetryPolicy, SourcifyApiClient},
+    chains::Chains,
+    session::Sessions,
+};
 
-use self::types::ApiRequest;
-use actix_web::{error::Error, web, web::Json};
+use self::types::{ApiRequest, CheckQuery, CheckResponse, ImportRequest};
+use crate::http_server::error::ApiError;
+use actix_web::{web, web::Json};
+use std::sync::Arc;
 
-use super::VerificationResponse;
+use super::{count_verification_request, VerificationResponse};
 
+/// Verifies the contract using Sourcify API
+#[utoipa::path(
+    post,
+    operation_id = "verify_sourcify",
+    path = "/api/v1/sourcify/verify",
+    request_body = ApiRequest,
+    responses(
+        (status = 200, description = "Verification has been finished", body = VerificationResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "sourcify"
+)]
 pub async fn verify(
     sourcify_client: web::Data<SourcifyApiClient>,
     params: Json<ApiRequest>,
-) -> Result<Json<VerificationResponse>, Error> {
-    let response =
-        api::verify_using_sourcify_client(sourcify_client.into_inner(), params.into_inner())
-            .await?;
-    Ok(Json(response))
+) -> Result<Json<VerificationResponse>, ApiError> {
+    let response = verify_using_client(sourcify_client.into_inner(), params.into_inner()).await;
+    count_verification_request("sourcify", &response);
+    response.map(Json)
+}
+
+pub(crate) async fn verify_using_client(
+    sourcify_client: Arc<SourcifyApiClient>,
+    params: ApiRequest,
+) -> Result<VerificationResponse, ApiError> {
+    api::verify_using_sourcify_client(sourcify_client, params).await
+}
+
+/// Returns whether the contracts are verified by Sourcify and the type of their matches
+#[utoipa::path(
+    get,
+    operation_id = "check_sourcify",
+    path = "/api/v1/sourcify/check",
+    params(CheckQuery),
+    responses(
+        (status = 200, description = "Statuses of the contracts", body = CheckResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "sourcify"
+)]
+pub async fn check(
+    sourcify_client: web::Data<SourcifyApiClient>,
+    query: web::Query<CheckQuery>,
+) -> Result<Json<CheckResponse>, ApiError> {
+    api::check_using_sourcify_client(sourcify_client.into_inner(), query.into_inner())
+        .await
+        .map(Json)
+}
+
+/// Returns the result for the contract already verified by Sourcify
+#[utoipa::path(
+    post,
+    operation_id = "import_sourcify",
+    path = "/api/v1/sourcify/import",
+    request_body = ImportRequest,
+    responses(
+        (status = 200, description = "The contract has been imported or is not verified", body = VerificationResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "sourcify"
+)]
+pub async fn import(
+    sourcify_client: web::Data<SourcifyApiClient>,
+    params: Json<ImportRequest>,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    api::import_using_sourcify_client(sourcify_client.into_inner(), params.into_inner())
+        .await
+        .map(Json)
 }
@@ -0,0 +1,372 @@
+//! Verification of several contracts at once through Sourcify session API.
+//!
+//! Sourcify identifies its sessions by cookies, which are kept by the service,
+//! so clients refer to the sessions by their ids only. The cookies are kept in
+//! memory of the instance that opened the session, so with several replicas
+//! requests of a session have to be routed to the same instance (sticky routing).
+
+use super::{
+    api::{self, ApiSession, SourcifyApi},
+    types::{
+        ApiSessionData, ApiSessionResponse, ApiSessionVerifyContract, SessionContract,
+        SessionFilesRequest, SessionResponse, SessionVerifyRequest,
+    },
+    SourcifyApiClient,
+};
+use crate::http_server::error::{ApiError, ErrorCode};
+use actix_web::{
+    web::{self, Json},
+    HttpResponse,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Sessions opened by the clients. Sessions unused for longer than `ttl` are dropped,
+/// and no more than `max_sessions` sessions are kept at once.
+pub struct Sessions {
+    ttl: Duration,
+    max_sessions: usize,
+    sessions: parking_lot::Mutex<HashMap<String, Session>>,
+}
+
+struct Session {
+    /// `None` while the session is being opened in Sourcify
+    cookie: Option<String>,
+    last_used: Instant,
+}
+
+/// Slot reserved for a session being opened. The slot is released on drop,
+/// unless the session has been opened.
+struct Reservation<'a> {
+    sessions: &'a Sessions,
+    id: Option<String>,
+}
+
+impl Reservation<'_> {
+    fn open(mut self, cookie: String) -> String {
+        let id = self.id.take().expect("reservation is opened once");
+        self.sessions.sessions.lock().insert(
+            id.clone(),
+            Session {
+                cookie: Some(cookie),
+                last_used: Instant::now(),
+            },
+        );
+        id
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.sessions.remove(&id);
+        }
+    }
+}
+
+impl Sessions {
+    pub fn new(ttl: Duration, max_sessions: usize) -> Self {
+        Self {
+            ttl,
+            max_sessions,
+            sessions: Default::default(),
+        }
+    }
+
+    /// Reserves a slot for a new session before it's opened in Sourcify, so that
+    /// Sourcify is not called in vain and an opened session always fits.
+    fn reserve(&self) -> Result<Reservation<'_>, ApiError> {
+        let id = format!("{:032x}", rand::random::<u128>());
+        let mut sessions = self.sessions.lock();
+        sessions.retain(|_, session| session.last_used.elapsed() < self.ttl);
+        if sessions.len() >= self.max_sessions {
+            return Err(ApiError::new(
+                ErrorCode::TooManySessions,
+                format!(
+                    "too many open sessions ({}), close unused sessions or try again later",
+                    sessions.len()
+                ),
+            ));
+        }
+        sessions.insert(
+            id.clone(),
+            Session {
+                cookie: None,
+                last_used: Instant::now(),
+            },
+        );
+        Ok(Reservation {
+            sessions: self,
+            id: Some(id),
+        })
+    }
+
+    fn cookie(&self, id: &str) -> Result<String, ApiError> {
+        let mut sessions = self.sessions.lock();
+        sessions.retain(|_, session| session.last_used.elapsed() < self.ttl);
+        match sessions.get_mut(id) {
+            Some(Session {
+                cookie: Some(cookie),
+                last_used,
+            }) => {
+                *last_used = Instant::now();
+                Ok(cookie.clone())
+            }
+            _ => Err(ApiError::new(
+                ErrorCode::SessionNotFound,
+                format!("session {} doesn't exist or has expired", id),
+            )),
+        }
+    }
+
+    fn update_cookie(&self, id: &str, cookie: Option<String>) {
+        if let (Some(cookie), Some(session)) = (cookie, self.sessions.lock().get_mut(id)) {
+            session.cookie = Some(cookie);
+        }
+    }
+
+    fn remove(&self, id: &str) {
+        self.sessions.lock().remove(id);
+    }
+}
+
+/// Uploads the files into a new session and returns the contracts found in them
+///
+/// Sessions are kept in memory of the instance that opened them, so with several
+/// replicas all requests of a session have to be routed to the same instance.
+#[utoipa::path(
+    post,
+    operation_id = "create_sourcify_session",
+    path = "/api/v1/sourcify/session",
+    request_body = SessionFilesRequest,
+    responses(
+        (status = 200, description = "Contracts of the session", body = SessionResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+        (status = 503, description = "Too many open sessions", body = ApiError),
+    ),
+    tag = "sourcify"
+)]
+pub async fn create(
+    sourcify_client: web::Data<SourcifyApiClient>,
+    sessions: web::Data<Sessions>,
+    params: Json<SessionFilesRequest>,
+) -> Result<Json<SessionResponse>, ApiError> {
+    create_session(sourcify_client.as_ref(), &sessions, params.into_inner())
+        .await
+        .map(Json)
+}
+
+/// Returns the contracts of the session
+#[utoipa::path(
+    get,
+    operation_id = "get_sourcify_session",
+    path = "/api/v1/sourcify/session/{session_id}",
+    responses(
+        (status = 200, description = "Contracts of the session", body = SessionResponse),
+        (status = 404, description = "Session not found", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "sourcify"
+)]
+pub async fn get(
+    sourcify_client: web::Data<SourcifyApiClient>,
+    sessions: web::Data<Sessions>,
+    session_id: web::Path<String>,
+) -> Result<Json<SessionResponse>, ApiError> {
+    let session_id = session_id.into_inner();
+    let cookie = sessions.cookie(&session_id)?;
+    let session = sourcify_client
+        .session_data_request(&cookie)
+        .await
+        .map_err(|err| ApiError::new(ErrorCode::SourcifyUnavailable, err))?;
+    session_response(&sessions, session_id, session).map(Json)
+}
+
+/// Uploads more files (e.g. the missing sources) into the session
+#[utoipa::path(
+    post,
+    operation_id = "add_sourcify_session_files",
+    path = "/api/v1/sourcify/session/{session_id}/files",
+    request_body = SessionFilesRequest,
+    responses(
+        (status = 200, description = "Contracts of the session", body = SessionResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Session not found", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "sourcify"
+)]
+pub async fn add_files(
+    sourcify_client: web::Data<SourcifyApiClient>,
+    sessions: web::Data<Sessions>,
+    session_id: web::Path<String>,
+    params: Json<SessionFilesRequest>,
+) -> Result<Json<SessionResponse>, ApiError> {
+    let session_id = session_id.into_inner();
+    let cookie = sessions.cookie(&session_id)?;
+    let session = sourcify_client
+        .session_files_request(Some(&cookie), &params.files)
+        .await
+        .map_err(|err| ApiError::new(ErrorCode::SourcifyUnavailable, err))?;
+    session_response(&sessions, session_id, session).map(Json)
+}
+
+/// Verifies the chosen contracts of the session at their addresses
+#[utoipa::path(
+    post,
+    operation_id = "verify_sourcify_session",
+    path = "/api/v1/sourcify/session/{session_id}/verify",
+    request_body = SessionVerifyRequest,
+    responses(
+        (status = 200, description = "Contracts of the session with verification statuses", body = SessionResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Session not found", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "sourcify"
+)]
+pub async fn verify(
+    sourcify_client: web::Data<SourcifyApiClient>,
+    sessions: web::Data<Sessions>,
+    session_id: web::Path<String>,
+    params: Json<SessionVerifyRequest>,
+) -> Result<Json<SessionResponse>, ApiError> {
+    verify_session(
+        sourcify_client.as_ref(),
+        &sessions,
+        session_id.into_inner(),
+        params.into_inner(),
+    )
+    .await
+    .map(Json)
+}
+
+/// Closes the session
+#[utoipa::path(
+    delete,
+    operation_id = "delete_sourcify_session",
+    path = "/api/v1/sourcify/session/{session_id}",
+    responses(
+        (status = 204, description = "The session has been closed"),
+        (status = 404, description = "Session not found", body = ApiError),
+        (status = 500, description = "Internal error", body = ApiError),
+    ),
+    tag = "sourcify"
+)]
+pub async fn delete(
+    sourcify_client: web::Data<SourcifyApiClient>,
+    sessions: web::Data<Sessions>,
+    session_id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let cookie = sessions.cookie(&session_id)?;
+    sessions.remove(&session_id);
+    // the session expires in Sourcify anyway, so failures are not reported
+    if let Err(err) = sourcify_client.session_clear_request(&cookie).await {
+        log::warn!("couldn't clear Sourcify session: {}", err);
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn create_session(
+    sourcify_client: &impl SourcifyApi,
+    sessions: &Sessions,
+    params: SessionFilesRequest,
+) -> Result<SessionResponse, ApiError> {
+    let reservation = sessions.reserve()?;
+    let session = sourcify_client
+        .session_files_request(None, &params.files)
+        .await
+        .map_err(|err| ApiError::new(ErrorCode::SourcifyUnavailable, err))?;
+    // errors are reported by Sourcify without opening a session
+    let data = session_data(session.response)?;
+    let cookie = session.cookie.ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::SourcifyInvalidResponse,
+            "Sourcify hasn't opened a session",
+        )
+    })?;
+    contracts_response(reservation.open(cookie), data)
+}
+
+async fn verify_session(
+    sourcify_client: &impl SourcifyApi,
+    sessions: &Sessions,
+    session_id: String,
+    params: SessionVerifyRequest,
+) -> Result<SessionResponse, ApiError> {
+    let cookie = sessions.cookie(&session_id)?;
+    let mut contracts = Vec::with_capacity(params.contracts.len());
+    for contract in &params.contracts {
+        let chain_id = sourcify_client
+            .chains()
+            .resolve(sourcify_client, &contract.chain)
+            .await?;
+        contracts.push(ApiSessionVerifyContract {
+            verification_id: &contract.verification_id,
+            address: &contract.address,
+            chain_id,
+        });
+    }
+    let session = sourcify_client
+        .session_verify_request(&cookie, &contracts)
+        .await
+        .map_err(|err| ApiError::new(ErrorCode::SourcifyUnavailable, err))?;
+    session_response(sessions, session_id, session)
+}
+
+fn session_response(
+    sessions: &Sessions,
+    session_id: String,
+    session: ApiSession,
+) -> Result<SessionResponse, ApiError> {
+    sessions.update_cookie(&session_id, session.cookie);
+    contracts_response(session_id, session_data(session.response)?)
+}
+
+fn session_data(response: ApiSessionResponse) -> Result<ApiSessionData, ApiError> {
+    match response {
+        ApiSessionResponse::Data(data) => Ok(data),
+        ApiSessionResponse::Error { error } => {
+            Err(ApiError::new(ErrorCode::SourcifyValidationFailed, error))
+        }
+        ApiSessionResponse::ValidationErrors { message, errors } => Err(ApiError::new(
+            ErrorCode::SourcifyValidationFailed,
+            format!("{}: {:?}", message, errors),
+        )),
+    }
+}
+
+fn contracts_response(
+    session_id: String,
+    data: ApiSessionData,
+) -> Result<SessionResponse, ApiError> {
+    let contracts = data
+        .contracts
+        .into_iter()
+        .map(|contract| {
+            let match_type = match contract.status.as_deref() {
+                // the reason of the failed verification is in the status message
+                None | Some("error") => None,
+                Some(status) => api::match_type(status)?,
+            };
+            Ok(SessionContract {
+                match_type,
+                verification_id: contract.verification_id,
+                name: contract.name,
+                compiled_path: contract.compiled_path,
+                address: contract.address,
+                chain: contract.chain_id,
+                missing_files: contract.files.missing.into_keys().collect(),
+                message: contract.status_message,
+            })
+        })
+        .collect::<Result<_, ApiError>>()?;
+    Ok(SessionResponse {
+        session_id,
+        contracts,
+        unused_files: data.unused,
+    })
+}
@@ -1,9 +1,11 @@
+use crate::MatchType;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
 
 // This struct is used as input for our endpoint and as
 // input for sourcify endpoint at the same time
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiRequest {
     pub address: String,
@@ -12,9 +14,80 @@ pub struct ApiRequest {
     pub chosen_contract: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// Source files and metadata.json by their names
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub struct Files(pub BTreeMap<String, String>);
 
+#[derive(Debug, Clone, Deserialize, PartialEq, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CheckQuery {
+    /// The chain (network) the contracts were deployed to
+    pub chain: String,
+    /// Comma separated addresses of the contracts
+    pub addresses: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct CheckResponse {
+    pub contracts: Vec<ContractStatus>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct ContractStatus {
+    pub address: String,
+    /// Is `null` if the contract is not verified by Sourcify
+    pub match_type: Option<MatchType>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct ImportRequest {
+    pub address: String,
+    pub chain: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct SessionFilesRequest {
+    pub files: Files,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct SessionVerifyRequest {
+    pub contracts: Vec<SessionVerifyContract>,
+}
+
+/// Contract of the session to be verified at the specified address
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct SessionVerifyContract {
+    pub verification_id: String,
+    pub address: String,
+    pub chain: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct SessionResponse {
+    pub session_id: String,
+    /// Contracts found in the uploaded metadata files
+    pub contracts: Vec<SessionContract>,
+    /// Uploaded files not used by any of the contracts
+    pub unused_files: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct SessionContract {
+    /// Identifies the contract in verification requests of the session
+    pub verification_id: String,
+    pub name: String,
+    pub compiled_path: String,
+    pub address: Option<String>,
+    pub chain: Option<String>,
+    /// Sources required by the metadata which haven't been uploaded yet
+    pub missing_files: Vec<String>,
+    /// Is `null` until the contract is verified
+    pub match_type: Option<MatchType>,
+    /// Reason of the failure of the last verification attempt
+    pub message: Option<String>,
+}
+
 // Definition of sourcify.dev API response
 // https://docs.sourcify.dev/docs/api/server/v1/verify/
 #[derive(Deserialize)]
@@ -40,6 +113,85 @@ pub(super) struct ResultItem {
     pub storage_timestamp: Option<String>,
 }
 
+// https://docs.sourcify.dev/docs/api/server/check-by-addresses/
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(super) enum ApiCheckResponse {
+    Checked(Vec<CheckItem>),
+    ValidationErrors {
+        message: String,
+        errors: Vec<FieldError>,
+    },
+}
+
+#[derive(Deserialize)]
+pub(super) struct CheckItem {
+    pub address: String,
+    pub status: String,
+}
+
+// https://docs.sourcify.dev/docs/api/server/get-chains/
+#[derive(Deserialize)]
+pub(super) struct ApiChain {
+    #[serde(rename = "chainId")]
+    pub chain_id: u64,
+    #[serde(default = "supported_by_default")]
+    pub supported: bool,
+}
+
+fn supported_by_default() -> bool {
+    true
+}
+
+// https://docs.sourcify.dev/docs/api/server/session-verification/
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(super) enum ApiSessionResponse {
+    Data(ApiSessionData),
+    Error {
+        error: String,
+    },
+    ValidationErrors {
+        message: String,
+        errors: Vec<FieldError>,
+    },
+}
+
+#[derive(Deserialize)]
+pub(super) struct ApiSessionData {
+    pub contracts: Vec<ApiSessionContract>,
+    #[serde(default)]
+    pub unused: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct ApiSessionContract {
+    pub verification_id: String,
+    pub name: String,
+    pub compiled_path: String,
+    pub address: Option<String>,
+    pub chain_id: Option<String>,
+    pub status: Option<String>,
+    pub status_message: Option<String>,
+    #[serde(default)]
+    pub files: ApiSessionContractFiles,
+}
+
+#[derive(Deserialize, Default)]
+pub(super) struct ApiSessionContractFiles {
+    #[serde(default)]
+    pub missing: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct ApiSessionVerifyContract<'a> {
+    pub verification_id: &'a str,
+    pub address: &'a str,
+    pub chain_id: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub(super) struct FieldError {
     field: String,
@@ -54,15 +206,31 @@ pub(super) struct ApiFilesResponse {
 #[derive(Deserialize, Debug)]
 pub(super) struct FileItem {
     pub name: String,
+    /// Path of the file in the repository, e.g.
+    /// `/repository/contracts/full_match/<chain>/<address>/sources/contracts/Token.sol`
+    #[serde(default)]
+    pub path: Option<String>,
     pub content: String,
 }
 
+impl FileItem {
+    /// Sources are named by their paths inside the `sources` folder, as the names
+    /// are just the last components of the paths and may be the same for several sources.
+    fn file_name(self) -> (String, String) {
+        let source_path = self.path.as_deref().and_then(|path| {
+            let (_, contract_path) = path.split_once("_match/")?;
+            let (_, source_path) = contract_path.split_once("/sources/")?;
+            Some(source_path.to_string())
+        });
+        (source_path.unwrap_or(self.name), self.content)
+    }
+}
+
 impl TryFrom<ApiFilesResponse> for Files {
     type Error = anyhow::Error;
 
     fn try_from(response: ApiFilesResponse) -> Result<Self, Self::Error> {
-        let files_map =
-            BTreeMap::from_iter(response.files.into_iter().map(|f| (f.name, f.content)));
+        let files_map = BTreeMap::from_iter(response.files.into_iter().map(FileItem::file_name));
         Ok(Files(files_map))
     }
 }
@@ -1,22 +1,28 @@
+pub mod error;
 pub mod handlers;
 mod routers;
 
 pub use self::routers::{configure_router, AppRouter, Router};
 
-use crate::config::Config;
-use actix_web::{App, HttpServer};
-use std::sync::Arc;
+use actix_web::{dev::Server, App, HttpServer};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
-pub async fn run(config: Config) -> std::io::Result<()> {
-    let socket_addr = config.server.addr;
+/// Binds HTTP server to the address. The returned server runs while being awaited.
+///
+/// Process signals are not handled by the server, so it should be stopped through
+/// its [`handle`](Server::handle). On graceful stop running requests are given
+/// `shutdown_timeout` to finish.
+pub fn run(
+    socket_addr: SocketAddr,
+    app_router: Arc<AppRouter>,
+    shutdown_timeout: Duration,
+) -> std::io::Result<Server> {
     log::info!("Verification server is starting at {}", socket_addr);
-    let app_router = Arc::new(
-        AppRouter::new(config)
-            .await
-            .expect("couldn't initialize the app"),
-    );
-    HttpServer::new(move || App::new().configure(configure_router(&*app_router)))
-        .bind(socket_addr)?
-        .run()
-        .await
+    Ok(
+        HttpServer::new(move || App::new().configure(configure_router(&*app_router)))
+            .disable_signals()
+            .shutdown_timeout(shutdown_timeout.as_secs())
+            .bind(socket_addr)?
+            .run(),
+    )
 }
@@ -0,0 +1,37 @@
+use actix_web::web;
+
+use super::Router;
+use crate::{
+    compiler::Compilers,
+    http_server::handlers::admin::{self, AdminToken},
+    solidity::AnyFetcher,
+};
+
+pub struct AdminRouter {
+    token: web::Data<AdminToken>,
+    compilers: web::Data<Compilers<AnyFetcher>>,
+}
+
+impl AdminRouter {
+    pub fn new(token: String, compilers: web::Data<Compilers<AnyFetcher>>) -> Self {
+        Self {
+            token: web::Data::new(AdminToken(token)),
+            compilers,
+        }
+    }
+}
+
+impl Router for AdminRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            .app_data(self.token.clone())
+            .app_data(self.compilers.clone())
+            .service(
+                web::scope("/compilers")
+                    .route("", web::get().to(admin::downloaded_compilers))
+                    .route("/prefetch", web::post().to(admin::prefetch))
+                    .route("/refresh", web::post().to(admin::refresh))
+                    .route("/{version}", web::delete().to(admin::evict)),
+            );
+    }
+}
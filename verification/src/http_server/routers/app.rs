@@ -1,34 +1,90 @@
-use super::{configure_router, Router, SolidityRouter, SourcifyRouter};
-use crate::{config::Config, http_server::handlers::status};
+use super::{configure_router, AdminRouter, Router, SolidityRouter, SourcifyRouter};
+use crate::{
+    compiler::Compilers,
+    config::Config,
+    http_server::{
+        error::{json_error_handler, query_error_handler},
+        handlers::sourcify::SourcifyApiClient,
+        handlers::{jobs, metrics, openapi, status},
+    },
+    scheduler::Scheduler,
+    solidity::AnyFetcher,
+};
 use actix_web::web;
 
 pub struct AppRouter {
     solidity: Option<SolidityRouter>,
     sourcify: Option<SourcifyRouter>,
+    admin: Option<AdminRouter>,
+    readiness: web::Data<status::Readiness>,
+    scheduler: web::Data<Scheduler>,
 }
 
 impl AppRouter {
     pub async fn new(config: Config) -> anyhow::Result<Self> {
+        crate::metrics::initialize();
+        let scheduler = web::Data::new(Scheduler::default());
         let solidity = match config.solidity.enabled {
             false => None,
-            true => Some(SolidityRouter::new(config.solidity).await?),
+            true => Some(SolidityRouter::new(config.solidity, &scheduler).await?),
         };
         let sourcify = config
             .sourcify
             .enabled
             .then(|| SourcifyRouter::new(config.sourcify));
-        Ok(Self { solidity, sourcify })
+        let admin = match (config.admin.token, &solidity) {
+            (Some(token), Some(solidity)) => Some(AdminRouter::new(token, solidity.compilers())),
+            _ => None,
+        };
+        let readiness = web::Data::new(status::Readiness::new(
+            solidity.as_ref().map(|router| router.compilers()),
+            sourcify.as_ref().map(|router| router.api_client()),
+        ));
+        Ok(Self {
+            solidity,
+            sourcify,
+            admin,
+            readiness,
+            scheduler,
+        })
+    }
+
+    /// Cancels running compilations and stops background jobs,
+    /// waiting for the running ones to finish.
+    pub async fn shutdown(&self) {
+        if let Some(compilers) = self.compilers() {
+            compilers.cancel_compilations();
+        }
+        self.scheduler.shutdown().await
+    }
+
+    pub(crate) fn compilers(&self) -> Option<web::Data<Compilers<AnyFetcher>>> {
+        self.solidity.as_ref().map(|router| router.compilers())
+    }
+
+    pub(crate) fn sourcify_client(&self) -> Option<web::Data<SourcifyApiClient>> {
+        self.sourcify.as_ref().map(|router| router.api_client())
     }
 }
 
 impl Router for AppRouter {
     fn register_routes(&self, service_config: &mut web::ServiceConfig) {
         service_config
+            .app_data(self.readiness.clone())
+            .app_data(self.scheduler.clone())
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
             .route("/health", web::get().to(status::status))
+            .route("/ready", web::get().to(status::readiness))
+            .route("/jobs", web::get().to(jobs::jobs))
+            .route("/metrics", web::get().to(metrics::metrics))
+            .route("/openapi.json", web::get().to(openapi::openapi))
+            .route("/swagger-ui", web::get().to(openapi::swagger_ui))
             .service(
                 web::scope("/api/v1")
                     .service(web::scope("/solidity").configure(configure_router(&self.solidity)))
-                    .service(web::scope("/sourcify").configure(configure_router(&self.sourcify))),
+                    .service(web::scope("/sourcify").configure(configure_router(&self.sourcify)))
+                    .service(web::scope("/admin").configure(configure_router(&self.admin))),
             );
     }
 }
@@ -1,10 +1,11 @@
+mod admin;
 mod app;
 mod solidity;
 mod sourcify;
 
 pub use self::app::AppRouter;
 
-use self::{solidity::SolidityRouter, sourcify::SourcifyRouter};
+use self::{admin::AdminRouter, solidity::SolidityRouter, sourcify::SourcifyRouter};
 
 pub trait Router {
     fn register_routes(&self, service_config: &mut actix_web::web::ServiceConfig);
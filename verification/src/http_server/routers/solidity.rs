@@ -2,29 +2,50 @@ use actix_web::web;
 
 use super::Router;
 use crate::{
-    compiler::Compilers,
+    compiler::{CompilationCache, Compilers},
     config::SolidityConfiguration,
-    http_server::handlers::{multi_part, standard_json, version_list},
-    solidity::CompilerFetcher,
+    http_server::handlers::{admin, metadata, multi_part, standard_json, version_list},
+    scheduler::Scheduler,
+    solidity::AnyFetcher,
 };
+use std::num::NonZeroUsize;
 
 pub struct SolidityRouter {
-    compilers: web::Data<Compilers<CompilerFetcher>>,
+    compilers: web::Data<Compilers<AnyFetcher>>,
 }
 
 impl SolidityRouter {
-    pub async fn new(config: SolidityConfiguration) -> anyhow::Result<Self> {
-        let fetcher = CompilerFetcher::new(
-            config.compilers_list_url,
-            Some(config.refresh_versions_schedule),
-            "compilers/".into(),
-        )
-        .await?;
-        let compilers = Compilers::new(fetcher);
+    pub async fn new(config: SolidityConfiguration, scheduler: &Scheduler) -> anyhow::Result<Self> {
+        let fetcher = AnyFetcher::from_config(&config).await;
+        if let AnyFetcher::List(fetcher) = &fetcher {
+            fetcher.spawn_refresh_job(scheduler);
+        }
+        let mut compilers = Compilers::new(fetcher);
+        if config.compilation_cache.enabled {
+            let bytes = |size_mb: NonZeroUsize| {
+                NonZeroUsize::new(size_mb.get().saturating_mul(1024 * 1024)).expect("Is not zero")
+            };
+            compilers = compilers.with_compilation_cache(CompilationCache::new(
+                bytes(config.compilation_cache.max_size_mb),
+                config.compilation_cache.folder,
+                bytes(config.compilation_cache.max_folder_size_mb),
+            ));
+        }
+        if !config.prefetch_versions.is_empty() {
+            log::info!("prefetching compilers: {:?}", config.prefetch_versions);
+            let prefetched = admin::prefetch_versions(&compilers, &config.prefetch_versions).await;
+            for (requested, err) in prefetched.failed {
+                log::warn!("couldn't prefetch compiler {}: {}", requested, err);
+            }
+        }
         Ok(Self {
             compilers: web::Data::new(compilers),
         })
     }
+
+    pub fn compilers(&self) -> web::Data<Compilers<AnyFetcher>> {
+        self.compilers.clone()
+    }
 }
 
 impl Router for SolidityRouter {
@@ -34,7 +55,8 @@ impl Router for SolidityRouter {
             .service(
                 web::scope("/verify")
                     .route("/multiple-files", web::post().to(multi_part::verify))
-                    .route("/standard-json", web::post().to(standard_json::verify)),
+                    .route("/standard-json", web::post().to(standard_json::verify))
+                    .route("/metadata", web::post().to(metadata::verify)),
             )
             .route("/versions", web::get().to(version_list::get_version_list));
     }
@@ -3,30 +3,60 @@ use actix_web::web;
 use super::Router;
 use crate::{
     config::SourcifyConfiguration,
-    http_server::handlers::sourcify::{self, SourcifyApiClient},
+    http_server::handlers::sourcify::{
+        self, session, Chains, RetryPolicy, Sessions, SourcifyApiClient,
+    },
 };
+use std::time::Duration;
 
 pub struct SourcifyRouter {
     api_client: web::Data<SourcifyApiClient>,
+    sessions: web::Data<Sessions>,
 }
 
 impl SourcifyRouter {
     pub fn new(config: SourcifyConfiguration) -> Self {
-        let api_client = SourcifyApiClient::new(
-            config.api_url,
-            config.request_timeout,
-            config.verification_attempts,
-        );
+        let retry_policy = RetryPolicy {
+            attempts: config.verification_attempts,
+            initial_delay: Duration::from_millis(config.initial_retry_delay_ms),
+            max_delay: Duration::from_millis(config.max_retry_delay_ms),
+            deadline: Duration::from_secs(config.retry_deadline),
+        };
+        let chains = Chains::new(config.chains, Duration::from_secs(config.chains_cache_ttl));
+        let api_client =
+            SourcifyApiClient::new(config.api_url, config.request_timeout, retry_policy, chains);
         Self {
             api_client: web::Data::new(api_client),
+            sessions: web::Data::new(Sessions::new(
+                Duration::from_secs(config.session_ttl),
+                config.max_sessions,
+            )),
         }
     }
+
+    pub fn api_client(&self) -> web::Data<SourcifyApiClient> {
+        self.api_client.clone()
+    }
 }
 
 impl Router for SourcifyRouter {
     fn register_routes(&self, service_config: &mut web::ServiceConfig) {
         service_config
             .app_data(self.api_client.clone())
-            .route("/verify", web::post().to(sourcify::verify));
+            .app_data(self.sessions.clone())
+            .route("/verify", web::post().to(sourcify::verify))
+            .route("/check", web::get().to(sourcify::check))
+            .route("/import", web::post().to(sourcify::import))
+            .route("/session", web::post().to(session::create))
+            .route("/session/{session_id}", web::get().to(session::get))
+            .route("/session/{session_id}", web::delete().to(session::delete))
+            .route(
+                "/session/{session_id}/files",
+                web::post().to(session::add_files),
+            )
+            .route(
+                "/session/{session_id}/verify",
+                web::post().to(session::verify),
+            );
     }
 }
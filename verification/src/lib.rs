@@ -1,19 +1,31 @@
 mod cli;
-mod compiler;
+mod commands;
+pub mod compiler;
 mod config;
 mod consts;
+mod grpc_server;
 mod http_server;
+mod metrics;
 mod scheduler;
-mod solidity;
+mod server;
+pub mod solidity;
 mod types;
 
 #[cfg(test)]
 mod tests;
 
-pub use self::{cli::Args, config::Config};
+pub use self::config::{Config, FetcherKind, SolidityConfiguration};
 pub use ethers_core::types::Bytes as DisplayBytes;
 pub use http_server::{
     configure_router,
-    handlers::verification::{VerificationResponse, VerificationResult, VerificationStatus},
+    error::{ApiError, ErrorCode},
+    handlers::verification::{
+        solidity::types::VersionKind, MatchType, VerificationResponse, VerificationResult,
+        VerificationStatus,
+    },
     run as run_http_server, AppRouter, Router,
 };
+
+/// Entry point of the `verification` binary, not a part of the library API.
+#[doc(hidden)]
+pub use commands::run_cli;
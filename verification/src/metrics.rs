@@ -0,0 +1,77 @@
+//! Prometheus metrics collected by the service.
+//! All metrics are registered in the default registry and exposed at `/metrics` route.
+
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    Histogram, HistogramVec, IntCounter, IntCounterVec,
+};
+
+/// Buckets (in seconds) suitable for long-running operations,
+/// like compilations and compiler downloads.
+const LONG_OPERATION_BUCKETS: &[f64] =
+    &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0];
+
+lazy_static! {
+    pub static ref VERIFICATION_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "verification_requests_total",
+        "number of verification requests by route and outcome",
+        &["route", "status"]
+    )
+    .unwrap();
+    pub static ref COMPILATION_TIME: HistogramVec = register_histogram_vec!(
+        "verification_compilation_duration_seconds",
+        "time spent on contracts compilation by compiler version",
+        &["compiler_version"],
+        LONG_OPERATION_BUCKETS.to_vec()
+    )
+    .unwrap();
+    pub static ref COMPILER_DOWNLOAD_TIME: Histogram = register_histogram!(
+        "verification_compiler_download_duration_seconds",
+        "time spent on successful compiler downloads",
+        LONG_OPERATION_BUCKETS.to_vec()
+    )
+    .unwrap();
+    pub static ref COMPILER_DOWNLOAD_FAILURES: IntCounter = register_int_counter!(
+        "verification_compiler_download_failures_total",
+        "number of failed compiler downloads"
+    )
+    .unwrap();
+    pub static ref DOWNLOAD_CACHE: IntCounterVec = register_int_counter_vec!(
+        "verification_download_cache_requests_total",
+        "number of compiler requests to the download cache by result (hit or miss)",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref SOURCIFY_REQUEST_TIME: HistogramVec = register_histogram_vec!(
+        "verification_sourcify_request_duration_seconds",
+        "latency of requests to Sourcify API by request type",
+        &["request"]
+    )
+    .unwrap();
+    pub static ref SOURCIFY_RETRIES: IntCounterVec = register_int_counter_vec!(
+        "verification_sourcify_retries_total",
+        "number of retried requests to Sourcify API by request type",
+        &["request"]
+    )
+    .unwrap();
+    pub static ref VERSIONS_REFRESH: IntCounterVec = register_int_counter_vec!(
+        "verification_versions_refresh_total",
+        "number of compiler versions refresh job runs by result (success or failure)",
+        &["result"]
+    )
+    .unwrap();
+}
+
+/// Registers all the metrics, so that they are exposed
+/// even before the corresponding events happen.
+pub fn initialize() {
+    lazy_static::initialize(&VERIFICATION_REQUESTS);
+    lazy_static::initialize(&COMPILATION_TIME);
+    lazy_static::initialize(&COMPILER_DOWNLOAD_TIME);
+    lazy_static::initialize(&COMPILER_DOWNLOAD_FAILURES);
+    lazy_static::initialize(&DOWNLOAD_CACHE);
+    lazy_static::initialize(&SOURCIFY_REQUEST_TIME);
+    lazy_static::initialize(&SOURCIFY_RETRIES);
+    lazy_static::initialize(&VERSIONS_REFRESH);
+}
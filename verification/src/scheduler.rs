@@ -1,65 +1,409 @@
+//! Registry of named background jobs run on cron schedules.
+//!
+//! Failed runs are retried with exponential backoff before the next scheduled run,
+//! and the state of every job is available through [`Scheduler::jobs`].
+
+use chrono::{DateTime, Utc};
 use cron::Schedule;
 use futures::Future;
+use parking_lot::{Mutex, RwLock};
+use rand::Rng;
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::{sync::watch, task::JoinHandle};
 
-use chrono::Utc;
-
-pub fn spawn_job<F, Fut>(
-    schedule: Schedule,
-    job_name: &'static str,
-    mut run: F,
-) -> tokio::task::JoinHandle<()>
-where
-    F: (FnMut() -> Fut) + Send + 'static,
-    Fut: Future + Send + 'static,
-    <Fut as futures::Future>::Output: Send,
-{
-    tokio::spawn(async move {
-        loop {
-            let sleep_duration = time_till_next_call(&schedule);
-            log::debug!(
-                "scheduled next run of '{}' in {:?}",
-                job_name,
-                sleep_duration
-            );
-            tokio::time::sleep(sleep_duration).await;
-            run().await;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobOptions {
+    /// Delay before the first retry of a failed run, doubled after each consecutive failure
+    pub initial_backoff: Duration,
+    /// Maximum delay between retries. Retries never happen later than the next scheduled run
+    pub max_backoff: Duration,
+    /// Runs are delayed by a random duration up to the value,
+    /// so that several instances don't run the job at the same time
+    pub max_jitter: Duration,
+    /// If specified, the first run happens after the delay instead of the first scheduled time
+    pub first_run_delay: Option<Duration>,
+}
+
+impl Default for JobOptions {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5 * 60),
+            max_jitter: Duration::ZERO,
+            first_run_delay: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobResult {
+    Success,
+    Failure { error: String },
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct JobStatus {
+    pub name: String,
+    pub schedule: String,
+    pub running: bool,
+    /// Start time of the last run
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_result: Option<JobResult>,
+    pub consecutive_failures: u32,
+    /// `None` if the job is finished: the schedule has no upcoming runs or the scheduler is shut down
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+pub struct Scheduler {
+    jobs: Arc<RwLock<BTreeMap<String, JobStatus>>>,
+    handles: Mutex<Vec<JoinHandle<()>>>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            jobs: Default::default(),
+            handles: Default::default(),
+            shutdown: watch::channel(false).0,
+        }
+    }
+}
+
+impl Scheduler {
+    /// Spawns the job running on the schedule until the scheduler is shut down.
+    /// Job names are expected to be unique.
+    pub fn spawn<F, Fut>(&self, name: &str, schedule: Schedule, options: JobOptions, mut run: F)
+    where
+        F: (FnMut() -> Fut) + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        log::info!("spawn job '{}'", name);
+        let job = Job {
+            name: name.to_string(),
+            jobs: self.jobs.clone(),
+        };
+        let now = Utc::now();
+        let mut next = match options.first_run_delay {
+            Some(delay) => Some(
+                now + chrono::Duration::from_std(delay)
+                    .unwrap_or_else(|_| chrono::Duration::zero()),
+            ),
+            None => next_run(&schedule, &options, 0, now, jitter(&options)),
+        };
+        self.jobs.write().insert(
+            name.to_string(),
+            JobStatus {
+                name: name.to_string(),
+                schedule: schedule.to_string(),
+                running: false,
+                last_run: None,
+                last_result: None,
+                consecutive_failures: 0,
+                next_run: next,
+            },
+        );
+        let mut shutdown = self.shutdown.subscribe();
+        let handle = tokio::spawn(async move {
+            loop {
+                let scheduled = match next {
+                    Some(scheduled) => scheduled,
+                    None => {
+                        log::warn!("job '{}' has no upcoming runs", job.name);
+                        break;
+                    }
+                };
+                let delay = (scheduled - Utc::now()).to_std().unwrap_or_default();
+                log::debug!("scheduled next run of '{}' in {:?}", job.name, delay);
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = shutdown.changed() => break,
+                }
+
+                job.update(|status| {
+                    status.running = true;
+                    status.last_run = Some(Utc::now());
+                });
+                let result = run().await;
+                job.update(|status| {
+                    status.running = false;
+                    match result {
+                        Ok(()) => {
+                            status.consecutive_failures = 0;
+                            status.last_result = Some(JobResult::Success);
+                        }
+                        Err(err) => {
+                            status.consecutive_failures += 1;
+                            log::warn!(
+                                "job '{}' failed {} time(s) in a row: {:#}",
+                                status.name,
+                                status.consecutive_failures,
+                                err
+                            );
+                            status.last_result = Some(JobResult::Failure {
+                                error: format!("{:#}", err),
+                            });
+                        }
+                    }
+                });
+
+                next = next_run(
+                    &schedule,
+                    &options,
+                    job.consecutive_failures(),
+                    Utc::now(),
+                    jitter(&options),
+                );
+                job.update(|status| status.next_run = next);
+            }
+            job.update(|status| status.next_run = None);
+        });
+        self.handles.lock().push(handle);
+    }
+
+    /// Returns the state of all spawned jobs sorted by their names.
+    pub fn jobs(&self) -> Vec<JobStatus> {
+        self.jobs.read().values().cloned().collect()
+    }
+
+    /// Stops scheduling new runs and waits for the running ones to finish.
+    pub async fn shutdown(&self) {
+        let _ = self.shutdown.send(true);
+        let handles = std::mem::take(&mut *self.handles.lock());
+        for result in futures::future::join_all(handles).await {
+            if let Err(err) = result {
+                log::error!("job panicked: {}", err);
+            }
+        }
+    }
+}
+
+struct Job {
+    name: String,
+    jobs: Arc<RwLock<BTreeMap<String, JobStatus>>>,
+}
+
+impl Job {
+    fn update(&self, f: impl FnOnce(&mut JobStatus)) {
+        if let Some(status) = self.jobs.write().get_mut(&self.name) {
+            f(status)
         }
-    })
+    }
+
+    fn consecutive_failures(&self) -> u32 {
+        self.jobs
+            .read()
+            .get(&self.name)
+            .map(|status| status.consecutive_failures)
+            .unwrap_or_default()
+    }
+}
+
+fn jitter(options: &JobOptions) -> Duration {
+    if options.max_jitter.is_zero() {
+        Duration::ZERO
+    } else {
+        rand::thread_rng().gen_range(Duration::ZERO..=options.max_jitter)
+    }
 }
 
-fn time_till_next_call(schedule: &Schedule) -> std::time::Duration {
-    let default = std::time::Duration::from_millis(500);
-    let now = Utc::now();
+/// Returns the time of the next run: the next scheduled time or, after failures,
+/// the retry time if it comes earlier. `None` if the schedule has no upcoming times
+/// and there is nothing to retry.
+fn next_run(
+    schedule: &Schedule,
+    options: &JobOptions,
+    consecutive_failures: u32,
+    now: DateTime<Utc>,
+    jitter: Duration,
+) -> Option<DateTime<Utc>> {
+    let scheduled = schedule.after(&now).next();
+    let retry = (consecutive_failures > 0).then(|| {
+        let backoff = options
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(consecutive_failures - 1))
+            .min(options.max_backoff);
+        now + chrono::Duration::from_std(backoff).unwrap_or_else(|_| chrono::Duration::zero())
+    });
+    let next_run = match (scheduled, retry) {
+        (Some(scheduled), Some(retry)) => scheduled.min(retry),
+        (scheduled, retry) => scheduled.or(retry)?,
+    };
+    Some(next_run + chrono::Duration::from_std(jitter).unwrap_or_else(|_| chrono::Duration::zero()))
+}
 
-    schedule
-        .upcoming(Utc)
-        .next()
-        .map_or(default, |t| (t - now).to_std().unwrap_or(default))
+/// Returns the interval between the two upcoming runs of the schedule, if any.
+pub fn period(schedule: &Schedule) -> Option<std::time::Duration> {
+    let mut upcoming = schedule.upcoming(Utc);
+    let (first, second) = (upcoming.next()?, upcoming.next()?);
+    (second - first).to_std().ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::str::FromStr;
+    use std::{
+        str::FromStr,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().into()
+    }
 
     #[test]
-    fn next_call() {
-        assert!(
-            // every second
-            time_till_next_call(&Schedule::from_str("* * * * * * *").unwrap())
-                <= std::time::Duration::from_secs(1)
+    fn next_runs() {
+        let hourly = Schedule::from_str("0 0 * * * * *").unwrap();
+        let never = Schedule::from_str("0 0 0 1 1 * 2000").unwrap();
+        let options = JobOptions::default();
+        let now = at("2022-06-01T12:30:00Z");
+
+        for (schedule, failures, jitter, expected) in [
+            (&hourly, 0, 0, Some("2022-06-01T13:00:00Z")),
+            (&hourly, 0, 5, Some("2022-06-01T13:00:05Z")),
+            // retries with exponential backoff
+            (&hourly, 1, 0, Some("2022-06-01T12:30:01Z")),
+            (&hourly, 3, 0, Some("2022-06-01T12:30:04Z")),
+            // backoff is limited by the maximum
+            (&hourly, 10, 0, Some("2022-06-01T12:35:00Z")),
+            (&never, 0, 0, None),
+            (&never, 1, 0, Some("2022-06-01T12:30:01Z")),
+        ] {
+            assert_eq!(
+                next_run(
+                    schedule,
+                    &options,
+                    failures,
+                    now,
+                    Duration::from_secs(jitter)
+                ),
+                expected.map(at),
+                "failures: {}, jitter: {}",
+                failures,
+                jitter
+            );
+        }
+
+        // retries don't happen later than the next scheduled run
+        let every_15_seconds = Schedule::from_str("0/15 * * * * * *").unwrap();
+        assert_eq!(
+            next_run(&every_15_seconds, &options, 10, now, Duration::ZERO),
+            Some(at("2022-06-01T12:30:15Z"))
         );
+    }
 
-        assert!(
-            // every 15 seconds
-            time_till_next_call(&Schedule::from_str("0/15 * * * * * *").unwrap())
-                <= std::time::Duration::from_secs(15)
+    #[tokio::test]
+    async fn runs_and_retries_jobs() {
+        let scheduler = Scheduler::default();
+        let runs = Arc::new(AtomicU32::new(0));
+        let options = JobOptions {
+            initial_backoff: Duration::from_millis(10),
+            ..Default::default()
+        };
+        {
+            let runs = runs.clone();
+            // the first two runs fail and are retried before the next scheduled run
+            scheduler.spawn(
+                "test job",
+                Schedule::from_str("* * * * * * *").unwrap(),
+                options.clone(),
+                move || {
+                    let run = runs.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        if run < 2 {
+                            anyhow::bail!("run {} failed", run)
+                        }
+                        Ok(())
+                    }
+                },
+            );
+        }
+        // the job with no upcoming runs finishes immediately
+        scheduler.spawn(
+            "finished job",
+            Schedule::from_str("0 0 0 1 1 * 2000").unwrap(),
+            options,
+            || async { Ok(()) },
         );
 
-        assert!(
+        let status = |name: &str| {
+            scheduler
+                .jobs()
+                .into_iter()
+                .find(|status| status.name == name)
+                .unwrap()
+        };
+        for _ in 0..100 {
+            if status("test job").last_result == Some(JobResult::Success) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let test_job = status("test job");
+        assert_eq!(test_job.last_result, Some(JobResult::Success));
+        assert_eq!(test_job.consecutive_failures, 0);
+        assert!(test_job.last_run.is_some());
+        assert!(test_job.next_run.is_some());
+        // both retries happen before the next scheduled run
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(status("finished job").next_run, None);
+
+        scheduler.shutdown().await;
+        assert_eq!(status("test job").next_run, None, "job should be stopped");
+    }
+
+    #[tokio::test]
+    async fn runs_first_time_after_delay() {
+        let scheduler = Scheduler::default();
+        let runs = Arc::new(AtomicU32::new(0));
+        let options = JobOptions {
+            first_run_delay: Some(Duration::from_millis(10)),
+            ..Default::default()
+        };
+        {
+            let runs = runs.clone();
+            // the schedule has no upcoming runs, so the job runs only once
+            scheduler.spawn(
+                "delayed job",
+                Schedule::from_str("0 0 0 1 1 * 2000").unwrap(),
+                options,
+                move || {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    async { Ok(()) }
+                },
+            );
+        }
+        assert!(scheduler.jobs()[0].next_run.is_some());
+
+        for _ in 0..100 {
+            if scheduler.jobs()[0].next_run.is_none() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(scheduler.jobs()[0].last_result, Some(JobResult::Success));
+        assert_eq!(scheduler.jobs()[0].next_run, None);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn schedule_period() {
+        assert_eq!(
+            // every 15 seconds
+            period(&Schedule::from_str("0/15 * * * * * *").unwrap()),
+            Some(std::time::Duration::from_secs(15))
+        );
+        assert_eq!(
             // every hour
-            time_till_next_call(&Schedule::from_str("0 0 * * * * *").unwrap())
-                <= std::time::Duration::from_secs(60 * 60)
+            period(&Schedule::from_str("0 0 * * * * *").unwrap()),
+            Some(std::time::Duration::from_secs(60 * 60))
+        );
+        assert_eq!(
+            // only once, in the past
+            period(&Schedule::from_str("0 0 0 1 1 * 2000").unwrap()),
+            None
         );
     }
 }
@@ -0,0 +1,89 @@
+use crate::{grpc_server, http_server, AppRouter, Config};
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::oneshot, time::Instant};
+
+/// Runs HTTP server and, if configured, gRPC server sharing the same application state.
+///
+/// On SIGINT or SIGTERM the servers stop accepting new requests, running requests
+/// are given `shutdown_timeout` to finish, and then leftover compilations
+/// and background jobs are stopped.
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let http_addr = config.server.addr;
+    let grpc_addr = config.server.grpc_addr;
+    let shutdown_timeout = Duration::from_secs(config.server.shutdown_timeout);
+    let app_router = Arc::new(AppRouter::new(config).await?);
+
+    let http = http_server::run(http_addr, app_router.clone(), shutdown_timeout)?;
+    let http_handle = http.handle();
+    let (grpc_shutdown, grpc_shutdown_signal) = oneshot::channel::<()>();
+    let grpc = async {
+        if let Some(grpc_addr) = grpc_addr {
+            let signal = async {
+                let _ = grpc_shutdown_signal.await;
+            };
+            grpc_server::run_with_shutdown(grpc_addr, app_router.clone(), signal).await?
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+    let servers = async {
+        futures::try_join!(async { http.await.map_err(anyhow::Error::from) }, grpc).map(|_| ())
+    };
+    tokio::pin!(servers);
+
+    let result = tokio::select! {
+        result = &mut servers => result,
+        _ = shutdown_signal() => {
+            log::info!(
+                "shutting down, waiting up to {:?} for running requests to finish",
+                shutdown_timeout
+            );
+            let deadline = Instant::now() + shutdown_timeout;
+            let _ = grpc_shutdown.send(());
+            // the servers should be polled for the stop to complete
+            let (_, result) = futures::join!(
+                http_handle.stop(true),
+                tokio::time::timeout_at(deadline, &mut servers)
+            );
+            match result {
+                Ok(result) => result,
+                Err(_) => {
+                    log::warn!("running requests have not finished in time");
+                    Ok(())
+                }
+            }
+        }
+    };
+    app_router.shutdown().await;
+    log::info!("server is stopped");
+    result
+}
+
+/// Resolves once the process is asked to terminate.
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            log::error!("couldn't listen for SIGINT: {}", err);
+            std::future::pending::<()>().await
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                log::error!("couldn't listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,169 @@
+use super::{
+    compiler_fetcher::{self, check_folder_writable, CompilerInfo, ListTimeouts, VersionsStatus},
+    local_fetcher::{self, LocalFetcher},
+    svm_fetcher::{self, SvmFetcher},
+    CompilerFetcher,
+};
+use crate::{
+    compiler::{CompilerVersion, Fetcher, VersionList},
+    config::{FetcherKind, SolidityConfiguration},
+};
+use async_trait::async_trait;
+use std::{collections::HashSet, path::PathBuf, time::Duration};
+use thiserror::Error;
+
+/// Fetcher backend selected in the configuration.
+pub enum AnyFetcher {
+    /// Downloads compilers from the compilers lists
+    List(Box<CompilerFetcher>),
+    /// Installs compilers with svm
+    Svm(SvmFetcher),
+    /// Uses compilers already present in the folder
+    Local(LocalFetcher),
+}
+
+#[derive(Error, Debug)]
+pub enum AnyFetchError {
+    #[error(transparent)]
+    List(#[from] compiler_fetcher::FetchError),
+    #[error(transparent)]
+    Svm(#[from] svm_fetcher::FetchError),
+    #[error(transparent)]
+    Local(#[from] local_fetcher::FetchError),
+}
+
+impl AnyFetcher {
+    /// Creates the backend selected in the configuration. The compilers lists
+    /// are fetched once, refreshing them is left to [`CompilerFetcher::spawn_refresh_job`].
+    pub async fn from_config(config: &SolidityConfiguration) -> Self {
+        match config.fetcher {
+            FetcherKind::List => CompilerFetcher::new(
+                config
+                    .compilers_lists
+                    .iter()
+                    .cloned()
+                    .map(Into::into)
+                    .collect(),
+                Some(config.refresh_versions_schedule.clone()),
+                config.compilers_folder.clone(),
+                ListTimeouts {
+                    connect: Duration::from_secs(config.compilers_list_connect_timeout),
+                    request: Duration::from_secs(config.compilers_list_timeout),
+                },
+            )
+            .await
+            .into(),
+            FetcherKind::Svm => SvmFetcher::default().into(),
+            FetcherKind::Local => LocalFetcher::new(config.compilers_folder.clone()).into(),
+        }
+    }
+
+    /// Information about the compiler download, known only for the compilers lists.
+    pub fn compiler_info(&self, ver: &CompilerVersion) -> Option<CompilerInfo> {
+        match self {
+            AnyFetcher::List(fetcher) => fetcher.compiler_info(ver),
+            AnyFetcher::Svm(_) | AnyFetcher::Local(_) => None,
+        }
+    }
+
+    /// State of the compilers list. Backends without a refreshed list return `None`.
+    pub fn versions_status(&self) -> Option<VersionsStatus> {
+        match self {
+            AnyFetcher::List(fetcher) => Some(fetcher.versions_status()),
+            AnyFetcher::Svm(_) | AnyFetcher::Local(_) => None,
+        }
+    }
+
+    /// Fetches the compilers lists immediately. Other backends have nothing to refresh.
+    pub async fn refresh_versions(&self) -> anyhow::Result<()> {
+        match self {
+            AnyFetcher::List(fetcher) => fetcher.refresh_versions().await,
+            AnyFetcher::Svm(_) | AnyFetcher::Local(_) => Ok(()),
+        }
+    }
+
+    /// Whether fetched compilers may be removed, so that they are fetched again when needed.
+    /// Compilers of the local backend can't be fetched again, and the svm folder
+    /// is shared with other tools using svm on the same machine.
+    pub fn is_evictable(&self) -> bool {
+        matches!(self, AnyFetcher::List(_))
+    }
+
+    /// Versions present on disk, including the ones fetched before the restart.
+    pub async fn downloaded_versions(&self) -> HashSet<CompilerVersion> {
+        let folder = match self {
+            AnyFetcher::List(fetcher) => Some(fetcher.folder().clone()),
+            AnyFetcher::Local(fetcher) => Some(fetcher.folder().clone()),
+            AnyFetcher::Svm(_) => None,
+        };
+        // compilers folder of the list backend has the same layout as the local one
+        let versions = tokio::task::spawn_blocking(move || match folder {
+            Some(folder) => LocalFetcher::new(folder).all_versions(),
+            None => SvmFetcher::installed_versions(),
+        })
+        .await;
+        match versions {
+            Ok(versions) => versions.into_iter().collect(),
+            Err(err) => {
+                log::warn!("couldn't read downloaded compilers: {}", err);
+                HashSet::new()
+            }
+        }
+    }
+
+    /// Checks that compilers could be obtained from the backend folder:
+    /// the folder should be writable for downloads and readable for the local backend.
+    pub async fn check_folder(&self) -> std::io::Result<()> {
+        match self {
+            AnyFetcher::List(fetcher) => fetcher.check_folder_writable().await,
+            AnyFetcher::Svm(fetcher) => {
+                let folder = fetcher
+                    .folder()
+                    .ok_or_else(|| std::io::Error::other("home directory for svm is not found"))?;
+                check_folder_writable(folder).await
+            }
+            AnyFetcher::Local(fetcher) => tokio::fs::read_dir(fetcher.folder()).await.map(|_| ()),
+        }
+    }
+}
+
+#[async_trait]
+impl Fetcher for AnyFetcher {
+    type Error = AnyFetchError;
+    async fn fetch(&self, ver: &CompilerVersion) -> Result<PathBuf, Self::Error> {
+        let path = match self {
+            AnyFetcher::List(fetcher) => fetcher.fetch(ver).await?,
+            AnyFetcher::Svm(fetcher) => fetcher.fetch(ver).await?,
+            AnyFetcher::Local(fetcher) => fetcher.fetch(ver).await?,
+        };
+        Ok(path)
+    }
+}
+
+impl VersionList for AnyFetcher {
+    fn all_versions(&self) -> Vec<CompilerVersion> {
+        match self {
+            AnyFetcher::List(fetcher) => fetcher.all_versions(),
+            AnyFetcher::Svm(fetcher) => fetcher.all_versions(),
+            AnyFetcher::Local(fetcher) => fetcher.all_versions(),
+        }
+    }
+}
+
+impl From<CompilerFetcher> for AnyFetcher {
+    fn from(fetcher: CompilerFetcher) -> Self {
+        AnyFetcher::List(Box::new(fetcher))
+    }
+}
+
+impl From<SvmFetcher> for AnyFetcher {
+    fn from(fetcher: SvmFetcher) -> Self {
+        AnyFetcher::Svm(fetcher)
+    }
+}
+
+impl From<LocalFetcher> for AnyFetcher {
+    fn from(fetcher: LocalFetcher) -> Self {
+        AnyFetcher::Local(fetcher)
+    }
+}
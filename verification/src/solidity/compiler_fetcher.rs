@@ -1,20 +1,26 @@
 use crate::{
     compiler::{CompilerVersion, Fetcher, VersionList},
-    scheduler,
+    metrics,
+    scheduler::{self, JobOptions, Scheduler},
 };
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use cron::Schedule;
 use primitive_types::H256;
+use reqwest::{header, StatusCode};
+use sha2::{Digest, Sha256};
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, HashMap},
     fmt::Debug,
-    fs::{File, OpenOptions},
-    io::ErrorKind,
-    os::unix::prelude::OpenOptionsExt,
+    fs::File,
+    io::Write,
     path::{Path, PathBuf},
+    str::FromStr,
     sync::Arc,
+    time::Duration,
 };
 use thiserror::Error;
+use tokio::io::AsyncWriteExt;
 
 use url::Url;
 
@@ -45,6 +51,23 @@ mod json {
         Url(Url),
         Filename(String),
     }
+
+    /// Last fetched list persisted on disk. Unlike `List` contains absolute urls only.
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    pub struct PersistedList {
+        pub builds: Vec<PersistedCompilerInfo>,
+    }
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PersistedCompilerInfo {
+        #[serde(with = "serde_with::rust::display_fromstr")]
+        pub long_version: CompilerVersion,
+        pub url: Url,
+        pub sha256: H256,
+        #[serde(default)]
+        pub mirrors: Vec<Url>,
+    }
 }
 
 #[derive(Error, Debug)]
@@ -59,86 +82,337 @@ pub enum ListError {
 
 type CompilerVersionsMap = HashMap<CompilerVersion, CompilerInfo>;
 
+/// Source of the compiler versions list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListSource {
+    /// Url of the list.json file
+    pub url: Url,
+    /// When several lists contain the same version, the list with higher priority is used
+    pub priority: i32,
+    /// Base urls of mirrors serving the same files as the list.
+    /// Used only for compilers specified by the relative path in the list.
+    pub mirrors: Vec<Url>,
+}
+
+impl From<Url> for ListSource {
+    fn from(url: Url) -> Self {
+        Self {
+            url,
+            priority: 0,
+            mirrors: vec![],
+        }
+    }
+}
+
+/// Timeouts of the requests fetching the compilers lists, so that an unresponsive
+/// host delays neither the startup nor the refresh job indefinitely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListTimeouts {
+    /// Time to establish the connection
+    pub connect: Duration,
+    /// Total time of the request, including reading the list
+    pub request: Duration,
+}
+
+impl Default for ListTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(10),
+            request: Duration::from_secs(60),
+        }
+    }
+}
+
 #[derive(Default, Clone)]
-pub struct CompilerVersions(Arc<parking_lot::RwLock<CompilerVersionsMap>>);
+pub struct CompilerVersions {
+    /// Client the lists are fetched with, shared by the startup fetch and the refresh job
+    client: reqwest::Client,
+    versions: Arc<parking_lot::RwLock<CompilerVersionsMap>>,
+    /// Time of the last successful fetch of the versions list
+    last_refresh: Arc<parking_lot::RwLock<Option<DateTime<Utc>>>>,
+    /// File the last fetched list is persisted to
+    list_file: Option<PathBuf>,
+}
 
-#[derive(Debug, PartialEq)]
+/// Name of the file inside compilers folder the last fetched list is persisted to
+const PERSISTED_LIST_FILE: &str = "list.json";
+/// Delays between attempts to fetch the list if it was unavailable at startup or refresh failed
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+/// Schedule with no upcoming runs, used to retry the list fetch when it is not refreshed
+const NEVER_SCHEDULE: &str = "0 0 0 1 1 * 2000";
+/// Refresh job is delayed by up to a tenth of the refresh period, but not more than the value
+const MAX_REFRESH_JITTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CompilerInfo {
     pub url: Url,
     pub sha256: H256,
+    /// Alternative urls the compiler is downloaded from if `url` is unavailable
+    pub mirrors: Vec<Url>,
+}
+
+impl CompilerInfo {
+    fn urls(&self) -> impl Iterator<Item = &Url> {
+        std::iter::once(&self.url).chain(self.mirrors.iter())
+    }
+}
+
+/// Fetches all the lists and merges them into one.
+/// Fails only if none of the lists could be fetched.
+pub async fn try_fetch_versions(
+    client: &reqwest::Client,
+    sources: &[ListSource],
+) -> Result<CompilerVersionsMap, ListError> {
+    let mut sources: Vec<_> = sources.iter().collect();
+    // sort is stable, so lists with the same priority keep the configured order
+    sources.sort_by_key(|source| std::cmp::Reverse(source.priority));
+    let results =
+        futures::future::join_all(sources.iter().map(|source| fetch_list(client, source))).await;
+
+    let mut lists = Vec::new();
+    let mut first_error = None;
+    for (source, result) in sources.iter().zip(results) {
+        match result {
+            Ok(list) => lists.push(list),
+            Err(err) => {
+                log::warn!("couldn't fetch compilers list {}: {}", source.url, err);
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    match first_error {
+        Some(err) if lists.is_empty() => Err(err),
+        _ => Ok(merge_lists(lists)),
+    }
 }
 
-pub async fn try_fetch_versions(versions_list_url: &Url) -> Result<CompilerVersionsMap, ListError> {
-    let list_json_file: json::List = reqwest::get(versions_list_url.as_str())
+async fn fetch_list(
+    client: &reqwest::Client,
+    source: &ListSource,
+) -> Result<CompilerVersionsMap, ListError> {
+    let list_json_file: json::List = client
+        .get(source.url.as_str())
+        .send()
         .await
+        .and_then(|response| response.error_for_status())
         .map_err(ListError::ListJsonFetch)?
         .json()
         .await
         .map_err(ListError::ParseListJson)?;
-    try_parse_json_file(list_json_file, versions_list_url)
+    try_parse_json_file(list_json_file, source)
 }
 
 fn try_parse_json_file(
     list_json_file: json::List,
-    versions_list_url: &Url,
+    source: &ListSource,
 ) -> Result<CompilerVersionsMap, ListError> {
     let mut compiler_versions = HashMap::default();
     for json_compiler_info in list_json_file.builds {
         let version = json_compiler_info.long_version.clone();
-        let compiler_info = CompilerInfo::try_from((json_compiler_info, versions_list_url))
-            .map_err(ListError::Path)?;
+        let compiler_info =
+            CompilerInfo::try_from((json_compiler_info, source)).map_err(ListError::Path)?;
         compiler_versions.insert(version, compiler_info);
     }
     Ok(compiler_versions)
 }
 
-impl TryFrom<(json::CompilerInfo, &Url)> for CompilerInfo {
+/// Merges the lists ordered by descending priority.
+/// The same compiler found in lower priority lists is used as a mirror,
+/// while versions with mismatching hashes are ignored.
+fn merge_lists(lists: Vec<CompilerVersionsMap>) -> CompilerVersionsMap {
+    let mut merged = CompilerVersionsMap::default();
+    for list in lists {
+        for (version, info) in list {
+            match merged.entry(version) {
+                Entry::Vacant(entry) => {
+                    entry.insert(info);
+                }
+                Entry::Occupied(mut entry) if entry.get().sha256 == info.sha256 => {
+                    let existing = entry.get_mut();
+                    for url in info.urls() {
+                        if !existing.urls().any(|existing_url| existing_url == url) {
+                            existing.mirrors.push(url.clone());
+                        }
+                    }
+                }
+                Entry::Occupied(entry) => {
+                    log::debug!(
+                        "compiler {} has different hashes in the lists, {} is ignored",
+                        entry.key(),
+                        info.url
+                    );
+                }
+            }
+        }
+    }
+    merged
+}
+
+impl TryFrom<(json::CompilerInfo, &ListSource)> for CompilerInfo {
     type Error = url::ParseError;
 
     fn try_from(
-        (compiler_info, download_url): (json::CompilerInfo, &Url),
+        (compiler_info, source): (json::CompilerInfo, &ListSource),
     ) -> Result<Self, Self::Error> {
-        let url = match compiler_info.path {
-            json::DownloadPath::Url(url) => url,
-            // download_url ends with `.../list.json` but join() will replace this with `filename`
-            json::DownloadPath::Filename(filename) => download_url.join(&filename)?,
+        let (url, mirrors) = match compiler_info.path {
+            json::DownloadPath::Url(url) => (url, vec![]),
+            // source url ends with `.../list.json` but join() will replace this with `filename`
+            json::DownloadPath::Filename(filename) => {
+                let mirrors = source
+                    .mirrors
+                    .iter()
+                    .map(|mirror| mirror.join(&filename))
+                    .collect::<Result<_, _>>()?;
+                (source.url.join(&filename)?, mirrors)
+            }
         };
         Ok(Self {
             url,
             sha256: compiler_info.sha256,
+            mirrors,
         })
     }
 }
 
+fn read_list_file(path: &Path) -> anyhow::Result<CompilerVersionsMap> {
+    let list: json::PersistedList = serde_json::from_slice(&std::fs::read(path)?)?;
+    Ok(list
+        .builds
+        .into_iter()
+        .map(|info| {
+            let compiler_info = CompilerInfo {
+                url: info.url,
+                sha256: info.sha256,
+                mirrors: info.mirrors,
+            };
+            (info.long_version, compiler_info)
+        })
+        .collect())
+}
+
+fn write_list_file(path: &Path, versions: &CompilerVersionsMap) -> anyhow::Result<()> {
+    let builds = versions
+        .iter()
+        .map(|(version, info)| json::PersistedCompilerInfo {
+            long_version: version.clone(),
+            url: info.url.clone(),
+            sha256: info.sha256,
+            mirrors: info.mirrors.clone(),
+        })
+        .collect();
+    let content = serde_json::to_vec(&json::PersistedList { builds })?;
+    let folder = match path.parent() {
+        Some(folder) if !folder.as_os_str().is_empty() => folder,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(folder)?;
+    // write into a unique temporary file first, so that concurrent refreshes
+    // don't interfere and the list is never left partially written
+    let mut tmp = tempfile::NamedTempFile::new_in(folder)?;
+    tmp.write_all(&content)?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(path)?;
+    Ok(())
+}
+
 impl CompilerVersions {
-    fn spawn_refresh_job(self, versions_list_url: Url, cron_schedule: Schedule) {
-        log::info!("spawn version refresh job");
-        scheduler::spawn_job(cron_schedule, "refresh compiler versions", move || {
-            let versions_list_url = versions_list_url.clone();
-            let versions = self.clone();
-            async move {
-                let refresh_result = versions.refresh_versions(&versions_list_url).await;
-                if let Err(err) = refresh_result {
-                    log::error!("error during version refresh: {}", err);
-                };
+    fn new(list_file: Option<PathBuf>, timeouts: ListTimeouts) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(timeouts.connect)
+            .timeout(timeouts.request)
+            .build()
+            .expect("client configuration should be valid");
+        Self {
+            client,
+            list_file,
+            ..Default::default()
+        }
+    }
+
+    /// Replaces the versions with the last known list, if it was persisted.
+    fn load_persisted(&self) {
+        if let Some(list_file) = &self.list_file {
+            match read_list_file(list_file) {
+                Ok(versions) => {
+                    log::warn!(
+                        "using the last known compilers list with {} versions",
+                        versions.len()
+                    );
+                    *self.versions.write() = versions;
+                }
+                Err(err) => log::warn!(
+                    "couldn't load the last known compilers list from {:?}: {}",
+                    list_file,
+                    err
+                ),
             }
-        });
+        }
     }
 
-    async fn refresh_versions(&self, versions_list_url: &Url) -> anyhow::Result<()> {
+    /// If `fetch_now` is set, the first run happens right away instead of the first
+    /// scheduled time, and the failed runs are retried with exponential backoff.
+    fn spawn_refresh_job(
+        self,
+        sources: Vec<ListSource>,
+        cron_schedule: Schedule,
+        fetch_now: bool,
+        scheduler: &Scheduler,
+    ) {
+        let options = JobOptions {
+            initial_backoff: INITIAL_RETRY_DELAY,
+            max_backoff: MAX_RETRY_DELAY,
+            max_jitter: scheduler::period(&cron_schedule)
+                .map(|period| std::cmp::min(period / 10, MAX_REFRESH_JITTER))
+                .unwrap_or_default(),
+            first_run_delay: fetch_now.then_some(INITIAL_RETRY_DELAY),
+        };
+        scheduler.spawn(
+            "refresh compiler versions",
+            cron_schedule,
+            options,
+            move || {
+                let sources = sources.clone();
+                let versions = self.clone();
+                async move {
+                    let refresh_result = versions.refresh_versions(&sources).await;
+                    let label = if refresh_result.is_ok() {
+                        "success"
+                    } else {
+                        "failure"
+                    };
+                    metrics::VERSIONS_REFRESH.with_label_values(&[label]).inc();
+                    refresh_result
+                }
+            },
+        );
+    }
+
+    async fn refresh_versions(&self, sources: &[ListSource]) -> anyhow::Result<()> {
         log::info!("looking for new compilers versions");
-        let fetched_versions = try_fetch_versions(versions_list_url)
+        let fetched_versions = try_fetch_versions(&self.client, sources)
             .await
             .map_err(anyhow::Error::msg)?;
         let need_to_update = {
-            let versions = self.0.read();
-            fetched_versions != *versions
+            let versions = self.versions.read();
+            // the versions loaded from the disk are persisted again, as the file may be outdated
+            fetched_versions != *versions || self.last_refresh.read().is_none()
         };
+        *self.last_refresh.write() = Some(Utc::now());
         if need_to_update {
+            if let Some(list_file) = &self.list_file {
+                if let Err(err) = write_list_file(list_file, &fetched_versions) {
+                    log::warn!(
+                        "couldn't persist compilers list into {:?}: {}",
+                        list_file,
+                        err
+                    );
+                }
+            }
             let (old_len, new_len) = {
                 // we don't need to check condition again,
                 // we can just override the value
-                let mut versions = self.0.write();
+                let mut versions = self.versions.write();
                 let old_len = versions.len();
                 *versions = fetched_versions;
                 let new_len = versions.len();
@@ -158,33 +432,128 @@ impl CompilerVersions {
 
 #[derive(Default)]
 pub struct CompilerFetcher {
+    client: reqwest::Client,
     compiler_versions: CompilerVersions,
+    sources: Vec<ListSource>,
+    refresh_versions_schedule: Option<Schedule>,
     folder: PathBuf,
 }
 
+/// State of the compiler versions list reported by readiness checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionsStatus {
+    /// Number of compiler versions currently available
+    pub versions: usize,
+    /// Time of the last successful fetch of the versions list
+    pub last_refresh: Option<DateTime<Utc>>,
+    /// Whether the list has not been refreshed for more than two refresh periods
+    pub is_stale: bool,
+    /// Whether the list has not been fetched since the start,
+    /// so the last known list (or an empty one) is used instead
+    pub is_degraded: bool,
+}
+
 impl CompilerFetcher {
+    /// Never fails: if the lists are unavailable, the last known list persisted
+    /// in the folder is used (if any), until the lists are fetched again
+    /// by the job spawned with [`CompilerFetcher::spawn_refresh_job`].
     pub async fn new(
-        versions_list_url: Url,
+        sources: Vec<ListSource>,
         refresh_versions_schedule: Option<Schedule>,
         folder: PathBuf,
-    ) -> anyhow::Result<Self> {
-        let compiler_versions = try_fetch_versions(&versions_list_url)
-            .await
-            .map_err(anyhow::Error::msg)?;
+        list_timeouts: ListTimeouts,
+    ) -> Self {
         let compiler_versions =
-            CompilerVersions(Arc::new(parking_lot::RwLock::new(compiler_versions)));
-        if let Some(cron_schedule) = refresh_versions_schedule {
-            compiler_versions
-                .clone()
-                .spawn_refresh_job(versions_list_url.clone(), cron_schedule)
+            CompilerVersions::new(Some(folder.join(PERSISTED_LIST_FILE)), list_timeouts);
+        if let Err(err) = compiler_versions.refresh_versions(&sources).await {
+            log::error!("couldn't fetch compilers list: {}", err);
+            compiler_versions.load_persisted();
         }
-        Ok(Self {
+        Self {
+            client: reqwest::Client::new(),
             compiler_versions,
+            sources,
+            refresh_versions_schedule,
             folder,
-        })
+        }
+    }
+
+    /// Refreshes the lists on `refresh_versions_schedule`, if it is specified.
+    /// If the lists were unavailable at startup, they are fetched right away
+    /// and retried with exponential backoff until the first success.
+    pub(crate) fn spawn_refresh_job(&self, scheduler: &Scheduler) {
+        let fetch_now = self.compiler_versions.last_refresh.read().is_none();
+        let cron_schedule = match (self.refresh_versions_schedule.clone(), fetch_now) {
+            (Some(cron_schedule), _) => cron_schedule,
+            // the job stops after the first successful run
+            (None, true) => Schedule::from_str(NEVER_SCHEDULE).expect("valid schedule"),
+            (None, false) => return,
+        };
+        self.compiler_versions.clone().spawn_refresh_job(
+            self.sources.clone(),
+            cron_schedule,
+            fetch_now,
+            scheduler,
+        )
+    }
+
+    pub fn versions_status(&self) -> VersionsStatus {
+        let versions = self.compiler_versions.versions.read().len();
+        let last_refresh = *self.compiler_versions.last_refresh.read();
+        let max_age = self
+            .refresh_versions_schedule
+            .as_ref()
+            .and_then(scheduler::period)
+            .map(|period| period * 2);
+        let is_stale = match (last_refresh, max_age) {
+            (Some(last_refresh), Some(max_age)) => (Utc::now() - last_refresh)
+                .to_std()
+                .map(|age| age > max_age)
+                .unwrap_or(false),
+            (None, _) => true,
+            (_, None) => false,
+        };
+        VersionsStatus {
+            versions,
+            last_refresh,
+            is_stale,
+            is_degraded: last_refresh.is_none(),
+        }
+    }
+
+    /// Fetches the lists immediately, without waiting for the scheduled refresh.
+    pub async fn refresh_versions(&self) -> anyhow::Result<()> {
+        self.compiler_versions.refresh_versions(&self.sources).await
+    }
+
+    pub fn compiler_info(&self, ver: &CompilerVersion) -> Option<CompilerInfo> {
+        self.compiler_versions.versions.read().get(ver).cloned()
+    }
+
+    /// Folder compilers are downloaded into.
+    pub fn folder(&self) -> &PathBuf {
+        &self.folder
+    }
+
+    /// Checks that compilers could be downloaded into the folder.
+    pub async fn check_folder_writable(&self) -> std::io::Result<()> {
+        check_folder_writable(self.folder.clone()).await
     }
 }
 
+pub(super) async fn check_folder_writable(folder: PathBuf) -> std::io::Result<()> {
+    tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&folder)?;
+        // probes get unique names, so that concurrent checks don't interfere
+        let probe = tempfile::Builder::new()
+            .prefix(".write_probe")
+            .tempfile_in(&folder)?;
+        probe.close()
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
 #[derive(Error, Debug)]
 pub enum FetchError {
     #[error("version {0} not found")]
@@ -195,68 +564,190 @@ pub enum FetchError {
     File(std::io::Error),
     #[error("tokio sheduling error: {0}")]
     Shedule(tokio::task::JoinError),
+    #[error("downloaded file hash mismatch: expected {expected:?}, found {found:?}")]
+    HashMismatch { expected: H256, found: H256 },
 }
 
-#[cfg(target_family = "unix")]
-fn create_executable(path: &Path) -> Result<File, std::io::Error> {
-    OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .mode(0o777)
-        .open(path)
+impl FetchError {
+    /// Network errors, server errors and rate limiting are considered transient.
+    fn is_transient(&self) -> bool {
+        match self {
+            FetchError::Fetch(err) => err.status().is_none_or(|status| {
+                status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+            }),
+            _ => false,
+        }
+    }
 }
 
 #[async_trait]
 impl Fetcher for CompilerFetcher {
     type Error = FetchError;
     async fn fetch(&self, ver: &CompilerVersion) -> Result<PathBuf, Self::Error> {
-        let compiler_download_url = {
-            let compiler_versions = self.compiler_versions.0.read();
-            let compiler_info = compiler_versions
-                .get(ver)
-                .ok_or_else(|| FetchError::NotFound(ver.clone()))?;
-            compiler_info.url.clone()
-        };
+        let timer = metrics::COMPILER_DOWNLOAD_TIME.start_timer();
+        let result = self.download(ver).await;
+        match result {
+            Ok(_) => timer.observe_duration(),
+            Err(_) => {
+                timer.stop_and_discard();
+                metrics::COMPILER_DOWNLOAD_FAILURES.inc();
+            }
+        }
+        result
+    }
+}
+
+/// Number of attempts to download the compiler from each url
+const DOWNLOAD_ATTEMPTS: u32 = 3;
+/// Delay before the first retry, doubled after each failed attempt
+const DOWNLOAD_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+impl CompilerFetcher {
+    async fn download(&self, ver: &CompilerVersion) -> Result<PathBuf, FetchError> {
+        let compiler_info = self
+            .compiler_info(ver)
+            .ok_or_else(|| FetchError::NotFound(ver.clone()))?;
 
-        let response = reqwest::get(compiler_download_url)
-            .await
-            .map_err(FetchError::Fetch)?;
         let folder = self.folder.join(ver.to_string());
         let file = folder.join("solc");
-        let bytes = response.bytes().await.map_err(FetchError::Fetch)?;
-        {
-            let file = file.clone();
-            tokio::task::spawn_blocking(move || -> Result<(), Self::Error> {
-                std::fs::create_dir_all(&folder).map_err(FetchError::File)?;
-                std::fs::remove_file(file.as_path())
-                    .or_else(|e| {
-                        if e.kind() == ErrorKind::NotFound {
-                            Ok(())
-                        } else {
-                            Err(e)
-                        }
-                    })
-                    .map_err(FetchError::File)?;
-                let mut file = create_executable(file.as_path()).map_err(FetchError::File)?;
-                std::io::copy(&mut bytes.as_ref(), &mut file).map_err(FetchError::File)?;
-                Ok(())
-            })
-            .await
-            .map_err(FetchError::Shedule)??;
+        // compilers downloaded before the restart are reused, unless they are corrupted
+        if let Ok(sha256) = file_sha256(&file).await {
+            if sha256 == compiler_info.sha256 {
+                log::info!("reusing already downloaded compiler {}", ver);
+                return Ok(file);
+            }
         }
 
+        tokio::fs::create_dir_all(&folder)
+            .await
+            .map_err(FetchError::File)?;
+        let tmp_file = folder.join("solc.download");
+        self.download_with_mirrors(&compiler_info, &tmp_file)
+            .await?;
+        // rename is atomic, so the file is either absent or complete
+        tokio::fs::rename(&tmp_file, &file)
+            .await
+            .map_err(FetchError::File)?;
+        tokio::fs::File::open(&folder)
+            .await
+            .map_err(FetchError::File)?
+            .sync_all()
+            .await
+            .map_err(FetchError::File)?;
+
         Ok(file)
     }
+
+    /// Tries the main url first, and then falls back to the mirrors one by one.
+    /// Partially downloaded file is resumed from the next url, as all of them serve the same file.
+    async fn download_with_mirrors(
+        &self,
+        compiler_info: &CompilerInfo,
+        tmp_file: &Path,
+    ) -> Result<(), FetchError> {
+        let mut last_error = None;
+        for url in compiler_info.urls() {
+            let result = match self.download_with_retries(url, tmp_file).await {
+                Ok(()) => check_sha256(tmp_file, compiler_info.sha256).await,
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    log::warn!("couldn't download compiler from {}: {}", url, err);
+                    if let FetchError::HashMismatch { .. } = err {
+                        tokio::fs::remove_file(tmp_file)
+                            .await
+                            .map_err(FetchError::File)?;
+                    }
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error.expect("compiler info always contains at least one url"))
+    }
+
+    /// Retries transient errors with exponential backoff, resuming the download on each attempt.
+    async fn download_with_retries(&self, url: &Url, tmp_file: &Path) -> Result<(), FetchError> {
+        let mut delay = DOWNLOAD_RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            match self.download_into(url, tmp_file).await {
+                Err(err) if attempt < DOWNLOAD_ATTEMPTS && err.is_transient() => {
+                    log::warn!(
+                        "attempt {} to download compiler from {} failed, retrying in {:?}: {}",
+                        attempt,
+                        url,
+                        delay,
+                        err
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Streams the file into `tmp_file`. If the file already contains
+    /// some bytes, requests only the rest of the file from the server.
+    async fn download_into(&self, url: &Url, tmp_file: &Path) -> Result<(), FetchError> {
+        let downloaded = tokio::fs::metadata(tmp_file)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        let mut request = self.client.get(url.clone());
+        if downloaded > 0 {
+            request = request.header(header::RANGE, format!("bytes={}-", downloaded));
+        }
+        let response = request.send().await.map_err(FetchError::Fetch)?;
+        if downloaded > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            // the file has already been downloaded completely
+            return Ok(());
+        }
+        let mut response = response.error_for_status().map_err(FetchError::Fetch)?;
+
+        let mut options = tokio::fs::OpenOptions::new();
+        if response.status() == StatusCode::PARTIAL_CONTENT {
+            log::info!("resuming download from {} at {} bytes", url, downloaded);
+            options.append(true);
+        } else {
+            options.create(true).write(true).truncate(true).mode(0o777);
+        }
+        let mut file = options.open(tmp_file).await.map_err(FetchError::File)?;
+        while let Some(chunk) = response.chunk().await.map_err(FetchError::Fetch)? {
+            file.write_all(&chunk).await.map_err(FetchError::File)?;
+        }
+        file.sync_all().await.map_err(FetchError::File)?;
+        Ok(())
+    }
+}
+
+async fn file_sha256(path: &Path) -> std::io::Result<H256> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut File::open(path)?, &mut hasher)?;
+        Ok(H256::from_slice(&hasher.finalize()))
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+async fn check_sha256(path: &Path, expected: H256) -> Result<(), FetchError> {
+    let found = file_sha256(path).await.map_err(FetchError::File)?;
+    if found == expected {
+        Ok(())
+    } else {
+        Err(FetchError::HashMismatch { expected, found })
+    }
 }
 
 impl VersionList for CompilerFetcher {
     fn all_versions(&self) -> Vec<CompilerVersion> {
-        let compiler_versions = self.compiler_versions.0.read();
-        compiler_versions
-            .iter()
-            .map(|(ver, _)| ver.clone())
-            .collect()
+        let compiler_versions = self.compiler_versions.versions.read();
+        compiler_versions.keys().cloned().collect()
     }
 }
 
@@ -266,9 +757,9 @@ mod tests {
 
     use super::*;
     use ethers_solc::Solc;
-    use std::{env::temp_dir, str::FromStr};
+    use std::env::temp_dir;
     use wiremock::{
-        matchers::{method, path},
+        matchers::{header, method, path},
         Mock, MockServer, ResponseTemplate,
     };
 
@@ -361,7 +852,7 @@ mod tests {
     fn parse_versions() {
         let list_json_file: json::List = serde_json::from_str(DEFAULT_LIST_JSON).unwrap();
         let download_url = Url::from_str(DEFAULT_DOWNLOAD_PREFIX).expect("valid url");
-        let verions = try_parse_json_file(list_json_file, &download_url).unwrap();
+        let verions = try_parse_json_file(list_json_file, &download_url.into()).unwrap();
         assert_has_version(
             &verions,
             "0.8.15-nightly.2022.5.27+commit.095cc647",
@@ -382,18 +873,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn concurrent_list_file_writes() {
+        let list_json_file: json::List = serde_json::from_str(DEFAULT_LIST_JSON).unwrap();
+        let download_url = Url::from_str(DEFAULT_DOWNLOAD_PREFIX).expect("valid url");
+        let versions = try_parse_json_file(list_json_file, &download_url.into()).unwrap();
+        let folder = temp_dir().join("blockscout/verification/compiler_fetcher/list_file/");
+        let _ = std::fs::remove_dir_all(&folder);
+        let list_file = folder.join(PERSISTED_LIST_FILE);
+
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| write_list_file(&list_file, &versions).expect("write should succeed"));
+            }
+        });
+
+        let persisted = read_list_file(&list_file).expect("list file should be valid");
+        assert_eq!(persisted.len(), versions.len());
+        // temporary files are not left behind
+        assert_eq!(std::fs::read_dir(&folder).unwrap().count(), 1);
+    }
+
     #[tokio::test]
     async fn list_download_versions() {
         let config = Config::default();
         let fetcher = CompilerFetcher::new(
-            config.solidity.compilers_list_url,
+            config
+                .solidity
+                .compilers_lists
+                .into_iter()
+                .map(Into::into)
+                .collect(),
             None,
             std::env::temp_dir().join("blockscout/verification/compiler_fetcher/test/"),
+            ListTimeouts::default(),
         )
-        .await
-        .expect("list.json file should be valid");
+        .await;
 
-        for compiler_version in vec![
+        for compiler_version in [
             CompilerVersion::from_str("0.7.0+commit.9e61f92b").unwrap(),
             CompilerVersion::from_str("0.8.9+commit.e5eed63a").unwrap(),
         ] {
@@ -423,12 +940,14 @@ mod tests {
             .mount(&mock_server)
             .await;
         let fetcher = CompilerFetcher::new(
-            Url::parse(&mock_server.uri()).unwrap(),
+            vec![Url::parse(&mock_server.uri()).unwrap().into()],
             Some(Schedule::from_str("* * * * * * *").unwrap()),
             temp_dir(),
+            ListTimeouts::default(),
         )
-        .await
-        .expect("cannot initialize fetcher");
+        .await;
+        let scheduler = Scheduler::default();
+        fetcher.spawn_refresh_job(&scheduler);
         assert!(fetcher.all_versions().is_empty());
 
         // mock list.json server response with `DEFAULT_LIST_JSON`
@@ -445,5 +964,362 @@ mod tests {
             versions.contains(&CompilerVersion::from_str("0.4.13+commit.0fb4cb1a").unwrap()),
             "versions list doesn't have 0.4.13: {versions:?}",
         );
+        let status = fetcher.versions_status();
+        assert_eq!(status.versions, versions.len());
+        assert!(!status.is_stale, "versions list should be refreshed");
+    }
+
+    fn list_json(builds: &[(&str, &str, &str)]) -> String {
+        let builds: Vec<_> = builds
+            .iter()
+            .map(|(path, version, sha256)| {
+                serde_json::json!({ "path": path, "longVersion": version, "sha256": sha256 })
+            })
+            .collect();
+        serde_json::json!({ "builds": builds }).to_string()
+    }
+
+    const SHA_1: &str = "0x35708c1593f3daddae734065e361a839ee39d400825972fb3f50718495be82b1";
+    const SHA_2: &str = "0x791ee3a20adf6c5ab76cc889f13cca102f76eb0b7cf0da4a0b5b11dc46edf349";
+
+    #[tokio::test]
+    async fn merge_lists_by_priority() {
+        let mock_server = MockServer::start().await;
+        for (list, builds) in [
+            (
+                "/main/list.json",
+                list_json(&[
+                    ("solc-0.4.13", "0.4.13+commit.0fb4cb1a", SHA_1),
+                    ("solc-0.4.14", "0.4.14+commit.c2215d46", SHA_1),
+                ]),
+            ),
+            (
+                "/fork/list.json",
+                list_json(&[
+                    ("solc-0.4.13", "0.4.13+commit.0fb4cb1a", SHA_1),
+                    ("solc-0.4.14", "0.4.14+commit.c2215d46", SHA_2),
+                    (
+                        "solc-nightly",
+                        "0.8.15-nightly.2022.5.27+commit.095cc647",
+                        SHA_2,
+                    ),
+                ]),
+            ),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(list))
+                .respond_with(ResponseTemplate::new(200).set_body_string(builds))
+                .mount(&mock_server)
+                .await;
+        }
+        let url = |path: &str| Url::parse(&format!("{}{}", mock_server.uri(), path)).unwrap();
+        let sources = vec![
+            ListSource {
+                url: url("/fork/list.json"),
+                priority: 0,
+                mirrors: vec![],
+            },
+            ListSource {
+                url: url("/main/list.json"),
+                priority: 1,
+                mirrors: vec![url("/mirror/")],
+            },
+            ListSource {
+                url: url("/unavailable/list.json"),
+                priority: 2,
+                mirrors: vec![],
+            },
+        ];
+
+        let versions = try_fetch_versions(&reqwest::Client::new(), &sources)
+            .await
+            .expect("some lists are available");
+        let info = |ver| {
+            versions
+                .get(&CompilerVersion::from_str(ver).unwrap())
+                .unwrap()
+        };
+        assert_eq!(versions.len(), 3);
+        assert_eq!(
+            info("0.4.13+commit.0fb4cb1a"),
+            &CompilerInfo {
+                url: url("/main/solc-0.4.13"),
+                sha256: H256::from_str(SHA_1).unwrap(),
+                mirrors: vec![url("/mirror/solc-0.4.13"), url("/fork/solc-0.4.13")],
+            }
+        );
+        // the hash differs from the one in the main list, so the fork is not used as a mirror
+        assert_eq!(
+            info("0.4.14+commit.c2215d46"),
+            &CompilerInfo {
+                url: url("/main/solc-0.4.14"),
+                sha256: H256::from_str(SHA_1).unwrap(),
+                mirrors: vec![url("/mirror/solc-0.4.14")],
+            }
+        );
+        assert_eq!(
+            info("0.8.15-nightly.2022.5.27+commit.095cc647").url,
+            url("/fork/solc-nightly")
+        );
+
+        try_fetch_versions(&reqwest::Client::new(), &sources[2..])
+            .await
+            .expect_err("all lists are unavailable");
+    }
+
+    /// Hash of the "compiler" string compilers are mocked with
+    const COMPILER_SHA: &str = "0xe996bb0ea465fae70d3e3c66b3b6e02d33d2f1eb76d5958720578b6cf359cc2e";
+
+    fn empty_folder(name: &str) -> PathBuf {
+        let folder = temp_dir()
+            .join("blockscout/verification/compiler_fetcher/")
+            .join(name);
+        let _ = std::fs::remove_dir_all(&folder);
+        folder
+    }
+
+    async fn compiler_fetcher(
+        mock_server: &MockServer,
+        mirrors: &[&str],
+        folder: PathBuf,
+    ) -> CompilerFetcher {
+        Mock::given(method("GET"))
+            .and(path("/list.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(list_json(&[(
+                "solc-0.4.13",
+                "0.4.13+commit.0fb4cb1a",
+                COMPILER_SHA,
+            )])))
+            .mount(mock_server)
+            .await;
+        let url = |path: &str| Url::parse(&format!("{}{}", mock_server.uri(), path)).unwrap();
+        CompilerFetcher::new(
+            vec![ListSource {
+                url: url("/list.json"),
+                priority: 0,
+                mirrors: mirrors.iter().map(|mirror| url(mirror)).collect(),
+            }],
+            None,
+            folder,
+            ListTimeouts::default(),
+        )
+        .await
+    }
+
+    fn compiler_version() -> CompilerVersion {
+        CompilerVersion::from_str("0.4.13+commit.0fb4cb1a").unwrap()
+    }
+
+    #[tokio::test]
+    async fn download_falls_back_to_mirrors() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/solc-0.4.13"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/mirror/solc-0.4.13"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes("compiler"))
+            .mount(&mock_server)
+            .await;
+        let fetcher = compiler_fetcher(
+            &mock_server,
+            &["/unavailable/", "/mirror/"],
+            empty_folder("mirrors"),
+        )
+        .await;
+
+        let file = fetcher
+            .fetch(&compiler_version())
+            .await
+            .expect("compiler should be downloaded from the mirror");
+        assert_eq!(std::fs::read(file).unwrap(), b"compiler");
+    }
+
+    #[tokio::test]
+    async fn download_retries_transient_errors() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/solc-0.4.13"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/solc-0.4.13"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes("compiler"))
+            .mount(&mock_server)
+            .await;
+        let fetcher = compiler_fetcher(&mock_server, &[], empty_folder("retries")).await;
+
+        let file = fetcher
+            .fetch(&compiler_version())
+            .await
+            .expect("compiler should be downloaded after retries");
+        assert_eq!(std::fs::read(file).unwrap(), b"compiler");
+    }
+
+    #[tokio::test]
+    async fn download_checks_hash() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/solc-0.4.13"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes("corrupted"))
+            .mount(&mock_server)
+            .await;
+        let fetcher = compiler_fetcher(&mock_server, &[], empty_folder("hash")).await;
+
+        let err = fetcher
+            .fetch(&compiler_version())
+            .await
+            .expect_err("corrupted compiler should not be accepted");
+        assert!(
+            matches!(err, FetchError::HashMismatch { .. }),
+            "unexpected error: {}",
+            err
+        );
+        let folder = fetcher.folder.join(compiler_version().to_string());
+        assert!(!folder.join("solc").exists());
+        assert!(!folder.join("solc.download").exists());
+    }
+
+    #[tokio::test]
+    async fn download_resumes_and_reuses_files() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/solc-0.4.13"))
+            .and(header("range", "bytes=4-"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes("iler"))
+            .mount(&mock_server)
+            .await;
+        let fetcher = compiler_fetcher(&mock_server, &[], empty_folder("resume")).await;
+        // the previous download has been interrupted
+        let folder = fetcher.folder.join(compiler_version().to_string());
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("solc.download"), "comp").unwrap();
+
+        let file = fetcher
+            .fetch(&compiler_version())
+            .await
+            .expect("download should be resumed");
+        assert_eq!(std::fs::read(&file).unwrap(), b"compiler");
+        assert!(!folder.join("solc.download").exists());
+
+        // the compiler is not downloaded again after the restart
+        mock_server.reset().await;
+        let restarted = compiler_fetcher(&mock_server, &[], fetcher.folder.clone()).await;
+        let reused = restarted
+            .fetch(&compiler_version())
+            .await
+            .expect("downloaded compiler should be reused");
+        assert_eq!(reused, file);
+    }
+
+    #[tokio::test]
+    async fn starts_with_unavailable_list() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(DEFAULT_LIST_JSON))
+            .mount(&mock_server)
+            .await;
+        let sources = vec![ListSource::from(Url::parse(&mock_server.uri()).unwrap())];
+        let folder = temp_dir().join("blockscout/verification/compiler_fetcher/unavailable/");
+        let _ = std::fs::remove_dir_all(&folder);
+
+        // the fetched list is persisted
+        let fetcher = CompilerFetcher::new(
+            sources.clone(),
+            None,
+            folder.clone(),
+            ListTimeouts::default(),
+        )
+        .await;
+        let versions = fetcher.all_versions();
+        assert!(!versions.is_empty());
+        assert!(!fetcher.versions_status().is_degraded);
+
+        // the persisted list is used while the list is unavailable
+        mock_server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+        let fetcher = CompilerFetcher::new(
+            sources.clone(),
+            None,
+            folder.clone(),
+            ListTimeouts::default(),
+        )
+        .await;
+        assert_eq!(fetcher.all_versions().len(), versions.len());
+        let status = fetcher.versions_status();
+        assert!(status.is_degraded);
+        assert_eq!(status.last_refresh, None);
+
+        // starts with an empty list if there is no persisted one
+        let empty_fetcher = CompilerFetcher::new(
+            sources.clone(),
+            None,
+            folder.join("empty/"),
+            ListTimeouts::default(),
+        )
+        .await;
+        assert!(empty_fetcher.all_versions().is_empty());
+        assert!(empty_fetcher.versions_status().is_degraded);
+
+        // the list is fetched by the background job once it becomes available
+        let scheduler = Scheduler::default();
+        fetcher.spawn_refresh_job(&scheduler);
+        empty_fetcher.spawn_refresh_job(&scheduler);
+        assert_eq!(scheduler.jobs().len(), 2);
+        mock_server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes("{\"builds\": []}"))
+            .mount(&mock_server)
+            .await;
+        tokio::time::sleep(INITIAL_RETRY_DELAY * 3).await;
+        for fetcher in [fetcher, empty_fetcher] {
+            assert!(fetcher.all_versions().is_empty());
+            let status = fetcher.versions_status();
+            assert!(!status.is_degraded);
+            assert!(status.last_refresh.is_some());
+        }
+        scheduler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn starts_when_list_host_hangs() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(DEFAULT_LIST_JSON)
+                    .set_delay(Duration::from_secs(60)),
+            )
+            .mount(&mock_server)
+            .await;
+        let timeouts = ListTimeouts {
+            connect: Duration::from_secs(1),
+            request: Duration::from_millis(200),
+        };
+        let fetcher = tokio::time::timeout(
+            Duration::from_secs(5),
+            CompilerFetcher::new(
+                vec![Url::parse(&mock_server.uri()).unwrap().into()],
+                None,
+                empty_folder("hanging"),
+                timeouts,
+            ),
+        )
+        .await
+        .expect("startup should not wait for the unresponsive list");
+        assert!(fetcher.all_versions().is_empty());
+        assert!(fetcher.versions_status().is_degraded);
     }
 }
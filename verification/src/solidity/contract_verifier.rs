@@ -0,0 +1,94 @@
+use super::{InitializationError, VerificationError, Verifier};
+use crate::{
+    compiler::{CompilerVersion, Compilers, CompilersError, Fetcher},
+    VerificationResult,
+};
+use ethers_solc::{
+    artifacts::{BytecodeHash, SettingsMetadata},
+    CompilerInput,
+};
+use semver::VersionReq;
+use std::fmt::{Debug, Display};
+use thiserror::Error;
+
+const BYTECODE_HASHES: [BytecodeHash; 3] =
+    [BytecodeHash::Ipfs, BytecodeHash::None, BytecodeHash::Bzzr1];
+
+pub struct Input<'a> {
+    pub compiler_version: CompilerVersion,
+    pub compiler_input: CompilerInput,
+    pub creation_tx_input: &'a str,
+    pub deployed_bytecode: &'a str,
+    /// Whether the bytecode hash settings of the compiler input are unknown,
+    /// so all possible bytecode hashes should be tried. Standard JSON input
+    /// contains the correct bytecode hash already, other inputs don't specify it.
+    pub bruteforce_bytecode_hashes: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The provided bytecodes couldn't be parsed
+    #[error("{0}")]
+    Initialization(#[from] InitializationError),
+    /// The compiler couldn't be obtained or failed to compile the input
+    #[error("{0:#}")]
+    Compilers(#[from] CompilersError),
+    /// The contract was compiled, but its bytecode doesn't match.
+    /// Contains the error for the contract closest to the one being verified.
+    #[error("No contract could be verified with provided data: {0}")]
+    NoMatchingContracts(#[from] VerificationError),
+}
+
+/// Compiles the input and verifies that the resulting bytecode matches the provided one.
+pub async fn verify<T: Fetcher>(
+    compilers: &Compilers<T>,
+    mut input: Input<'_>,
+) -> Result<VerificationResult, Error>
+where
+    <T as Fetcher>::Error: Debug + Display,
+{
+    let verifier = Verifier::new(input.creation_tx_input, input.deployed_bytecode)?;
+
+    let bruteforce_metadata = settings_metadata(&input);
+
+    let mut closest_error = VerificationError::NoContracts;
+    for metadata in bruteforce_metadata {
+        input.compiler_input.settings.metadata = metadata;
+        let compiler_output = compilers
+            .compile(&input.compiler_version, &input.compiler_input)
+            .await?;
+        match verifier.verify(&compiler_output) {
+            Ok(verification_success) => {
+                return Ok(VerificationResult::from((
+                    input.compiler_input,
+                    input.compiler_version,
+                    verification_success,
+                )))
+            }
+            // Try other bytecode hashes if there is no matching contracts
+            Err(err) => {
+                closest_error = closest_error.closest(err);
+            }
+        }
+    }
+    Err(Error::NoMatchingContracts(closest_error))
+}
+
+/// Iterates through possible bytecode if required and creates
+/// a corresponding variants of settings metadata for each of them.
+///
+/// See "settings_metadata" (https://docs.soliditylang.org/en/v0.8.15/using-the-compiler.html?highlight=compiler%20input#input-description)
+fn settings_metadata(input: &Input<'_>) -> Vec<Option<SettingsMetadata>> {
+    if !input.bruteforce_bytecode_hashes {
+        [input.compiler_input.settings.metadata.clone()].into()
+    } else if VersionReq::parse("<0.6.0")
+        .unwrap()
+        .matches(input.compiler_version.version())
+    {
+        [None].into()
+    } else {
+        BYTECODE_HASHES
+            .map(|hash| Some(SettingsMetadata::from(hash)))
+            .into()
+    }
+}
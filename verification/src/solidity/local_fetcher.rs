@@ -0,0 +1,111 @@
+use crate::compiler::{CompilerVersion, Fetcher, VersionList};
+use async_trait::async_trait;
+use std::{path::PathBuf, str::FromStr};
+use thiserror::Error;
+
+/// Uses compilers already present in the folder, without downloading anything.
+///
+/// The folder has the layout compilers are downloaded into by
+/// [`CompilerFetcher`](super::CompilerFetcher): `<folder>/<version>/solc`,
+/// e.g. `compilers/v0.8.14+commit.80d49f37/solc`.
+pub struct LocalFetcher {
+    folder: PathBuf,
+}
+
+#[derive(Error, Debug)]
+pub enum FetchError {
+    #[error("version {0} not found")]
+    NotFound(CompilerVersion),
+}
+
+impl LocalFetcher {
+    pub fn new(folder: PathBuf) -> Self {
+        Self { folder }
+    }
+
+    pub fn folder(&self) -> &PathBuf {
+        &self.folder
+    }
+
+    fn compiler_path(&self, ver: &CompilerVersion) -> PathBuf {
+        self.folder.join(ver.to_string()).join("solc")
+    }
+}
+
+#[async_trait]
+impl Fetcher for LocalFetcher {
+    type Error = FetchError;
+    async fn fetch(&self, ver: &CompilerVersion) -> Result<PathBuf, Self::Error> {
+        let path = self.compiler_path(ver);
+        if path.is_file() {
+            Ok(path)
+        } else {
+            Err(FetchError::NotFound(ver.clone()))
+        }
+    }
+}
+
+impl VersionList for LocalFetcher {
+    /// The folder is read on every call, so compilers added
+    /// while the service is running become available immediately.
+    fn all_versions(&self) -> Vec<CompilerVersion> {
+        let entries = match std::fs::read_dir(&self.folder) {
+            Ok(entries) => entries,
+            Err(err) => {
+                log::warn!("couldn't read compilers folder {:?}: {}", self.folder, err);
+                return vec![];
+            }
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let version = CompilerVersion::from_str(entry.file_name().to_str()?).ok()?;
+                entry.path().join("solc").is_file().then_some(version)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+
+    #[tokio::test]
+    async fn uses_compilers_from_folder() {
+        let folder = temp_dir().join("blockscout/verification/local_fetcher/");
+        let _ = std::fs::remove_dir_all(&folder);
+        for (dir, file) in [
+            ("v0.8.14+commit.80d49f37", Some("solc")),
+            ("v0.8.15-nightly.2022.5.27+commit.095cc647", Some("solc")),
+            // folders without the compiler and with unknown names are ignored
+            ("v0.8.13+commit.abaa5c0e", None),
+            ("not-a-version", Some("solc")),
+        ] {
+            std::fs::create_dir_all(folder.join(dir)).unwrap();
+            if let Some(file) = file {
+                std::fs::write(folder.join(dir).join(file), "compiler").unwrap();
+            }
+        }
+        let fetcher = LocalFetcher::new(folder.clone());
+        let ver = |s| CompilerVersion::from_str(s).unwrap();
+
+        let mut versions = fetcher.all_versions();
+        versions.sort();
+        assert_eq!(
+            versions,
+            vec![
+                ver("0.8.14+commit.80d49f37"),
+                ver("0.8.15-nightly.2022.5.27+commit.095cc647"),
+            ]
+        );
+
+        let path = fetcher.fetch(&ver("0.8.14+commit.80d49f37")).await.unwrap();
+        assert_eq!(path, folder.join("v0.8.14+commit.80d49f37/solc"));
+        let err = fetcher
+            .fetch(&ver("0.8.13+commit.abaa5c0e"))
+            .await
+            .expect_err("compiler is missing");
+        assert!(matches!(err, FetchError::NotFound(_)));
+    }
+}
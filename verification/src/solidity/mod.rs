@@ -1,7 +1,38 @@
+//! Verification of Solidity contracts, usable without the server.
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use verification::{
+//!     compiler::{CompilerVersion, Compilers},
+//!     solidity::{self, Input, LocalFetcher},
+//! };
+//!
+//! let compilers = Compilers::new(LocalFetcher::new("compilers/".into()));
+//! let input = Input {
+//!     compiler_version: "v0.8.14+commit.80d49f37".parse()?,
+//!     compiler_input: serde_json::from_str(&std::fs::read_to_string("input.json")?)?,
+//!     creation_tx_input: "0x6080...",
+//!     deployed_bytecode: "0x6080...",
+//!     bruteforce_bytecode_hashes: false,
+//! };
+//! let result = solidity::verify(&compilers, input).await?;
+//! println!("verified {}", result.contract_name);
+//! # Ok(())
+//! # }
+//! ```
+
+mod any_fetcher;
 mod compiler_fetcher;
+mod contract_verifier;
+pub mod local_fetcher;
 pub mod svm_fetcher;
 mod verifier;
 
-pub use compiler_fetcher::CompilerFetcher;
+pub use any_fetcher::{AnyFetchError, AnyFetcher};
+pub use compiler_fetcher::{CompilerFetcher, FetchError, ListSource, ListTimeouts, VersionsStatus};
+pub use contract_verifier::{verify, Error, Input};
+pub use local_fetcher::LocalFetcher;
+pub use svm_fetcher::SvmFetcher;
 
+pub use verifier::{InitializationError, VerificationError};
 pub(crate) use verifier::{VerificationSuccess, Verifier};
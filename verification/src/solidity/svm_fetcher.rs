@@ -1,9 +1,12 @@
-use crate::compiler::{CompilerVersion, Fetcher};
+use crate::compiler::{CompilerVersion, Fetcher, ReleaseVersion, VersionList};
 use async_trait::async_trait;
-use ethers_solc::{error::SolcError, Solc};
-use std::path::PathBuf;
+use ethers_solc::{error::SolcError, Solc, RELEASES};
+use std::{path::PathBuf, str::FromStr};
 use thiserror::Error;
 
+/// Installs compilers with [svm](https://github.com/roynalnaruto/svm-rs),
+/// so they are shared with other tools using svm on the same machine.
+/// Only release versions known to svm are available.
 #[derive(Default)]
 pub struct SvmFetcher {}
 
@@ -13,18 +16,96 @@ pub enum FetchError {
     Svm(#[from] SolcError),
     #[error("nightly versions aren't supported in svm")]
     NightlyNotSupported,
+    #[error("version {0} not found")]
+    NotFound(CompilerVersion),
+}
+
+impl SvmFetcher {
+    /// Folder svm installs compilers into.
+    pub fn folder(&self) -> Option<PathBuf> {
+        Solc::svm_home()
+    }
+
+    /// Releases already installed into the svm folder.
+    pub fn installed_versions() -> Vec<CompilerVersion> {
+        let installed: Vec<_> = Solc::installed_versions()
+            .into_iter()
+            .map(semver::Version::from)
+            .collect();
+        Self::releases()
+            .into_iter()
+            .filter(|release| installed.contains(&release.version))
+            .map(CompilerVersion::Release)
+            .collect()
+    }
+
+    fn releases() -> Vec<ReleaseVersion> {
+        RELEASES
+            .0
+            .releases
+            .values()
+            .filter_map(|filename| release_from_filename(filename))
+            .collect()
+    }
+}
+
+/// Parses versions from svm release filenames, e.g. `solc-linux-amd64-v0.8.14+commit.80d49f37`
+fn release_from_filename(filename: &str) -> Option<ReleaseVersion> {
+    let (_, version) = filename.rsplit_once("-v")?;
+    ReleaseVersion::from_str(version).ok()
 }
 
 #[async_trait]
 impl Fetcher for SvmFetcher {
     type Error = FetchError;
     async fn fetch(&self, ver: &CompilerVersion) -> Result<PathBuf, Self::Error> {
-        match ver {
-            CompilerVersion::Release(ver) => Solc::install(&ver.version)
-                .await
-                .map(|x| x.solc)
-                .map_err(|err| FetchError::Svm(err.into())),
-            CompilerVersion::Nightly(_) => Err(FetchError::NightlyNotSupported),
+        let ver = match ver {
+            CompilerVersion::Release(release) => release,
+            CompilerVersion::Nightly(_) => return Err(FetchError::NightlyNotSupported),
+        };
+        // svm knows only one build of each version, so the commit should match it
+        if !Self::releases().contains(ver) {
+            return Err(FetchError::NotFound(CompilerVersion::Release(ver.clone())));
+        }
+        if let Some(solc) = Solc::find_svm_installed_version(ver.version.to_string())? {
+            return Ok(solc.solc);
+        }
+        Solc::install(&ver.version)
+            .await
+            .map(|x| x.solc)
+            .map_err(|err| FetchError::Svm(err.into()))
+    }
+}
+
+impl VersionList for SvmFetcher {
+    fn all_versions(&self) -> Vec<CompilerVersion> {
+        Self::releases()
+            .into_iter()
+            .map(CompilerVersion::Release)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_release_filenames() {
+        let release = |s| Some(ReleaseVersion::from_str(s).unwrap());
+        for (filename, expected) in [
+            (
+                "solc-linux-amd64-v0.8.14+commit.80d49f37",
+                release("0.8.14+commit.80d49f37"),
+            ),
+            (
+                "solc-macosx-amd64-v0.4.13+commit.0fb4cb1a",
+                release("0.4.13+commit.0fb4cb1a"),
+            ),
+            ("solc-v0.8.14", None),
+            ("solc", None),
+        ] {
+            assert_eq!(release_from_filename(filename), expected, "{}", filename);
         }
     }
 }
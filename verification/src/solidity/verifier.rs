@@ -15,7 +15,7 @@ use thiserror::Error;
 /// Errors that may occur during initial [`Verifier`] setup
 /// with input data provided by the requester.
 #[derive(Clone, Debug, PartialEq, Error)]
-pub(crate) enum InitializationError {
+pub enum InitializationError {
     #[error("creation transaction input is not a valid hex string")]
     InvalidCreationTxInput(String),
     #[error("deployed bytecode is not a valid hex string: {0}")]
@@ -28,7 +28,9 @@ pub(crate) enum InitializationError {
 
 /// Errors that may occur during bytecode comparison step.
 #[derive(Clone, Debug, Error)]
-enum VerificationError {
+pub enum VerificationError {
+    #[error("compilation output does not contain any contracts")]
+    NoContracts,
     #[error("deployed bytecode is invalid (most probably the contract is abstract and has no deployed bytecode): {0}")]
     InvalidDeployedBytecode(String),
     #[error("compiler versions included into metadata hash does not match: {0:?}")]
@@ -45,6 +47,32 @@ enum VerificationError {
     InternalError(String),
 }
 
+impl VerificationError {
+    /// How far the comparison went before the error occurred.
+    /// The higher the value, the closer the contract is to the one being verified.
+    fn closeness(&self) -> u8 {
+        match self {
+            VerificationError::NoContracts => 0,
+            VerificationError::InternalError(_) => 0,
+            VerificationError::InvalidDeployedBytecode(_) => 1,
+            VerificationError::MissedLibrary => 2,
+            VerificationError::CompilerVersionMismatch(_) => 3,
+            VerificationError::BytecodeMismatch(_) => 4,
+            VerificationError::ExtraDataMismatch(_) => 5,
+            VerificationError::InvalidConstructorArguments(_) => 6,
+        }
+    }
+
+    /// Returns the error of the two, which corresponds to the closest contract.
+    pub fn closest(self, other: Self) -> Self {
+        if other.closeness() > self.closeness() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
 /// The structure returned as a result when verification successes.
 /// Contains data needed to be sent back as a verification response.
 #[derive(Clone, Debug, PartialEq)]
@@ -90,7 +118,7 @@ impl<'b, C> Decode<'b, C> for MetadataHash {
         for _ in 0..number_of_elements {
             // try to parse the key
             match d.str() {
-                Ok(s) if s == "solc" => {
+                Ok("solc") => {
                     if solc.is_some() {
                         // duplicate keys are not allowed in CBOR (RFC 8949)
                         return Err(Error::custom(ParseMetadataHashError::DuplicateKeys));
@@ -423,23 +451,37 @@ impl Verifier {
     ///
     /// Iterates through all contracts received from local compilation and
     /// returns [`VerificationSuccess`] with file path and contract name
-    /// of succeeded contract, if any. Otherwise, returns [`None`].
-    pub fn verify(&self, output: CompilerOutput) -> Option<VerificationSuccess> {
-        for (path, contracts) in output.contracts {
+    /// of succeeded contract, if any. Otherwise, returns the error
+    /// of the contract which is the closest to the one being verified.
+    pub fn verify(&self, output: &CompilerOutput) -> Result<VerificationSuccess, VerificationError> {
+        let mut closest_error = VerificationError::NoContracts;
+        for (path, contracts) in &output.contracts {
             for (name, contract) in contracts {
-                // TODO: add logging in case if error is `VerificationError::InternalError`
-                if let Ok((abi, constructor_args)) = self.compare(&contract) {
-                    return Some(VerificationSuccess {
-                        file_path: path,
-                        contract_name: name,
-                        abi,
-                        constructor_args: constructor_args.map(DisplayBytes::from),
-                    });
+                match self.compare(contract) {
+                    Ok((abi, constructor_args)) => {
+                        return Ok(VerificationSuccess {
+                            file_path: path.clone(),
+                            contract_name: name.clone(),
+                            abi,
+                            constructor_args: constructor_args.map(DisplayBytes::from),
+                        });
+                    }
+                    Err(err) => {
+                        if let VerificationError::InternalError(message) = &err {
+                            log::warn!(
+                                "internal error while verifying {}:{}: {}",
+                                path,
+                                name,
+                                message
+                            );
+                        }
+                        closest_error = closest_error.closest(err);
+                    }
                 }
             }
         }
 
-        None
+        Err(closest_error)
     }
 
     /// Compares the result of local contract compilation with data specified on initialization.
@@ -561,19 +603,19 @@ mod verifier_initialization_tests {
     use super::*;
     use const_format::concatcp;
 
-    const DEFAULT_CONSTRUCTOR_ARGS: &'static str =
+    const DEFAULT_CONSTRUCTOR_ARGS: &str =
         "0000000000000000000000000000000000000000000000000000000000000fff";
     // {"ipfs": h'1220EB23CE2C13EA8739368F952F6C6A4B1F0623D147D2A19B6D4D26A61AB03FCD3E', "solc": 0.8.14}
-    const DEFAULT_ENCODED_METADATA_HASH: &'static str = "a2646970667358221220eb23ce2c13ea8739368f952f6c6a4b1f0623d147d2a19b6d4d26a61ab03fcd3e64736f6c634300080e0033";
-    const DEFAULT_BYTECODE_WITHOUT_METADATA_HASH: &'static str = "608060405234801561001057600080fd5b5060405161022038038061022083398101604081905261002f91610074565b600080546001600160a01b0319163390811782556040519091907f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a735908290a35061008d565b60006020828403121561008657600080fd5b5051919050565b6101848061009c6000396000f3fe608060405234801561001057600080fd5b50600436106100365760003560e01c8063893d20e81461003b578063a6f9dae11461005a575b600080fd5b600054604080516001600160a01b039092168252519081900360200190f35b61006d61006836600461011e565b61006f565b005b6000546001600160a01b031633146100c35760405162461bcd60e51b815260206004820152601360248201527221b0b63632b91034b9903737ba1037bbb732b960691b604482015260640160405180910390fd5b600080546040516001600160a01b03808516939216917f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a73591a3600080546001600160a01b0319166001600160a01b0392909216919091179055565b60006020828403121561013057600080fd5b81356001600160a01b038116811461014757600080fd5b939250505056fe";
-    const DEFAULT_DEPLOYED_BYTECODE_WITHOUT_METADATA_HASH: &'static str =  "608060405234801561001057600080fd5b50600436106100365760003560e01c8063893d20e81461003b578063a6f9dae11461005a575b600080fd5b600054604080516001600160a01b039092168252519081900360200190f35b61006d61006836600461011e565b61006f565b005b6000546001600160a01b031633146100c35760405162461bcd60e51b815260206004820152601360248201527221b0b63632b91034b9903737ba1037bbb732b960691b604482015260640160405180910390fd5b600080546040516001600160a01b03808516939216917f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a73591a3600080546001600160a01b0319166001600160a01b0392909216919091179055565b60006020828403121561013057600080fd5b81356001600160a01b038116811461014757600080fd5b939250505056fe";
+    const DEFAULT_ENCODED_METADATA_HASH: &str = "a2646970667358221220eb23ce2c13ea8739368f952f6c6a4b1f0623d147d2a19b6d4d26a61ab03fcd3e64736f6c634300080e0033";
+    const DEFAULT_BYTECODE_WITHOUT_METADATA_HASH: &str = "608060405234801561001057600080fd5b5060405161022038038061022083398101604081905261002f91610074565b600080546001600160a01b0319163390811782556040519091907f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a735908290a35061008d565b60006020828403121561008657600080fd5b5051919050565b6101848061009c6000396000f3fe608060405234801561001057600080fd5b50600436106100365760003560e01c8063893d20e81461003b578063a6f9dae11461005a575b600080fd5b600054604080516001600160a01b039092168252519081900360200190f35b61006d61006836600461011e565b61006f565b005b6000546001600160a01b031633146100c35760405162461bcd60e51b815260206004820152601360248201527221b0b63632b91034b9903737ba1037bbb732b960691b604482015260640160405180910390fd5b600080546040516001600160a01b03808516939216917f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a73591a3600080546001600160a01b0319166001600160a01b0392909216919091179055565b60006020828403121561013057600080fd5b81356001600160a01b038116811461014757600080fd5b939250505056fe";
+    const DEFAULT_DEPLOYED_BYTECODE_WITHOUT_METADATA_HASH: &str =  "608060405234801561001057600080fd5b50600436106100365760003560e01c8063893d20e81461003b578063a6f9dae11461005a575b600080fd5b600054604080516001600160a01b039092168252519081900360200190f35b61006d61006836600461011e565b61006f565b005b6000546001600160a01b031633146100c35760405162461bcd60e51b815260206004820152601360248201527221b0b63632b91034b9903737ba1037bbb732b960691b604482015260640160405180910390fd5b600080546040516001600160a01b03808516939216917f342827c97908e5e2f71151c08502a66d44b6f758e3ac2f1de95f02eb95f0a73591a3600080546001600160a01b0319166001600160a01b0392909216919091179055565b60006020828403121561013057600080fd5b81356001600160a01b038116811461014757600080fd5b939250505056fe";
 
-    const DEFAULT_CREATION_TX_INPUT: &'static str = concatcp!(
+    const DEFAULT_CREATION_TX_INPUT: &str = concatcp!(
         DEFAULT_BYTECODE_WITHOUT_METADATA_HASH,
         DEFAULT_ENCODED_METADATA_HASH,
         DEFAULT_CONSTRUCTOR_ARGS
     );
-    const DEFAULT_DEPLOYED_BYTECODE: &'static str = concatcp!(
+    const DEFAULT_DEPLOYED_BYTECODE: &str = concatcp!(
         DEFAULT_DEPLOYED_BYTECODE_WITHOUT_METADATA_HASH,
         DEFAULT_ENCODED_METADATA_HASH
     );
@@ -587,8 +629,8 @@ mod verifier_initialization_tests {
         );
 
         let verifier = Verifier::new(
-            &concatcp!("0x", DEFAULT_CREATION_TX_INPUT),
-            &concatcp!("0x", DEFAULT_DEPLOYED_BYTECODE),
+            concatcp!("0x", DEFAULT_CREATION_TX_INPUT),
+            concatcp!("0x", DEFAULT_DEPLOYED_BYTECODE),
         );
         assert!(verifier.is_ok(), "Initialization with \"0x\" prefix failed");
     }
@@ -816,7 +858,7 @@ mod metadata_hash_deserialization_tests {
         // 3 elements expected in the map but got only 2:
         // { "ipfs": b"1220BCC988B1311237F2C00CCD0BFBD8B01D24DC18F720603B0DE93FE6327DF53625", "solc": b'00080e' }
         let hex = "a3646970667358221220bcc988b1311237f2c00ccd0bfbd8b01d24dc18f720603b0de93fe6327df5362564736f6c634300080e";
-        let encoded = DisplayBytes::from_str(&hex).unwrap().0;
+        let encoded = DisplayBytes::from_str(hex).unwrap().0;
 
         // when
         let decoded = MetadataHash::from_cbor(encoded);
@@ -834,7 +876,7 @@ mod metadata_hash_deserialization_tests {
         // given
         // { "ipfs": b"1220BCC988B1311237F2C00CCD0BFBD8B01D24DC18F720603B0DE93FE6327DF53625", "solc": 123 } \
         let hex= "a2646970667358221220bcc988b1311237f2c00ccd0bfbd8b01d24dc18f720603b0de93fe6327df5362564736f6c63187B";
-        let encoded = DisplayBytes::from_str(&hex).unwrap().0;
+        let encoded = DisplayBytes::from_str(hex).unwrap().0;
 
         // when
         let decoded = MetadataHash::from_cbor(encoded);
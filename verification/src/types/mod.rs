@@ -1,3 +1,5 @@
 pub(crate) use self::mismatch::Mismatch;
+pub use self::verification_result::{MatchType, VerificationResult};
 
 mod mismatch;
+mod verification_result;
@@ -0,0 +1,75 @@
+use crate::{compiler::CompilerVersion, solidity::VerificationSuccess, DisplayBytes};
+use ethers_solc::CompilerInput;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct VerificationResult {
+    pub file_name: String,
+    pub contract_name: String,
+    pub compiler_version: String,
+    pub evm_version: String,
+    #[schema(value_type = Option<String>)]
+    pub constructor_arguments: Option<DisplayBytes>,
+    pub optimization: Option<bool>,
+    pub optimization_runs: Option<usize>,
+    pub contract_libraries: BTreeMap<String, String>,
+    pub abi: String,
+    pub sources: BTreeMap<String, String>,
+    /// Type of the Sourcify match. Is `None` for contracts verified by the service itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_type: Option<MatchType>,
+    /// Time the contract was stored in Sourcify repository, if it had been verified before.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_timestamp: Option<String>,
+}
+
+/// Whether the metadata hash of the deployed bytecode matches the one of the
+/// recompiled contract ("perfect") or only the executable part does ("partial").
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchType {
+    Perfect,
+    Partial,
+}
+
+impl From<(CompilerInput, CompilerVersion, VerificationSuccess)> for VerificationResult {
+    fn from(
+        (compiler_input, compiler_version, verification_success): (
+            CompilerInput,
+            CompilerVersion,
+            VerificationSuccess,
+        ),
+    ) -> Self {
+        VerificationResult {
+            file_name: verification_success.file_path,
+            contract_name: verification_success.contract_name,
+            compiler_version: compiler_version.to_string(),
+            evm_version: compiler_input
+                .settings
+                .evm_version
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "default".to_string()),
+            constructor_arguments: verification_success.constructor_args,
+            optimization: compiler_input.settings.optimizer.enabled,
+            optimization_runs: compiler_input.settings.optimizer.runs,
+            contract_libraries: compiler_input
+                .settings
+                .libraries
+                .libs
+                .into_values()
+                .flatten()
+                .collect(),
+            abi: serde_json::to_string(&verification_success.abi)
+                .expect("Is result of local compilation and, thus, should be always valid"),
+            sources: compiler_input
+                .sources
+                .into_iter()
+                .map(|(path, source)| (path.to_string_lossy().to_string(), source.content))
+                .collect(),
+            match_type: None,
+            storage_timestamp: None,
+        }
+    }
+}
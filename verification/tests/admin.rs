@@ -0,0 +1,161 @@
+use actix_web::{
+    http::StatusCode,
+    test::{self, TestRequest},
+    App,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use verification::{configure_router, ApiError, AppRouter, Config, ErrorCode};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const TOKEN: &str = "secret";
+const VERSION: &str = "v0.8.14+commit.80d49f37";
+/// Hash of the "compiler" string all the compilers are mocked with
+const COMPILER_SHA256: &str = "0xe996bb0ea465fae70d3e3c66b3b6e02d33d2f1eb76d5958720578b6cf359cc2e";
+
+async fn admin_app_router(
+    token: Option<&str>,
+    prefetch_versions: &[&str],
+) -> (MockServer, AppRouter) {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/list.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "builds": [
+                {
+                    "path": "solc-v0.8.14",
+                    "longVersion": "0.8.14+commit.80d49f37",
+                    "sha256": COMPILER_SHA256
+                },
+                {
+                    "path": "solc-v0.8.13",
+                    "longVersion": "0.8.13+commit.abaa5c0e",
+                    "sha256": COMPILER_SHA256
+                }
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+    for compiler in ["/solc-v0.8.14", "/solc-v0.8.13"] {
+        Mock::given(method("GET"))
+            .and(path(compiler))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes("compiler"))
+            .mount(&mock_server)
+            .await;
+    }
+
+    let mut config = Config::default();
+    config.sourcify.enabled = false;
+    config.admin.token = token.map(str::to_string);
+    config.solidity.prefetch_versions = prefetch_versions.iter().map(|v| v.to_string()).collect();
+    config.solidity.compilers_lists[0].url =
+        format!("{}/list.json", mock_server.uri()).parse().unwrap();
+    let app_router = AppRouter::new(config)
+        .await
+        .expect("couldn't initialize the app");
+    (mock_server, app_router)
+}
+
+fn authorized(request: TestRequest) -> TestRequest {
+    request.insert_header(("Authorization", format!("Bearer {}", TOKEN)))
+}
+
+#[actix_rt::test]
+async fn unauthorized_requests() {
+    let (_mock_server, app_router) = admin_app_router(Some(TOKEN), &[]).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    for request in [
+        TestRequest::get().uri("/api/v1/admin/compilers"),
+        TestRequest::get()
+            .uri("/api/v1/admin/compilers")
+            .insert_header(("Authorization", "Bearer wrong")),
+        TestRequest::post().uri("/api/v1/admin/compilers/refresh"),
+    ] {
+        let resp = request.send_request(&app).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body: ApiError = test::read_body_json(resp).await;
+        assert_eq!(body.code, ErrorCode::Unauthorized);
+    }
+}
+
+#[actix_rt::test]
+async fn manage_compilers() {
+    let (_mock_server, app_router) = admin_app_router(Some(TOKEN), &[]).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = authorized(TestRequest::post().uri("/api/v1/admin/compilers/prefetch"))
+        .set_json(json!({ "versions": ["0.8.14", "0.8.15"] }))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["downloaded"], json!({ "0.8.14": VERSION }));
+    assert_eq!(body["failed"]["0.8.15"]["code"], "COMPILER_NOT_FOUND");
+
+    let resp = authorized(TestRequest::get().uri("/api/v1/admin/compilers"))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    let compilers = body["compilers"].as_array().unwrap();
+    assert_eq!(compilers.len(), 1);
+    assert_eq!(compilers[0]["version"], VERSION);
+    assert_eq!(compilers[0]["size"], "compiler".len());
+
+    // versions are resolved the same way as for prefetch
+    for (version, evicted) in [("0.8.14", true), (VERSION, false)] {
+        let resp =
+            authorized(TestRequest::delete().uri(&format!("/api/v1/admin/compilers/{}", version)))
+                .send_request(&app)
+                .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({ "evicted": evicted }));
+    }
+
+    let resp = authorized(TestRequest::get().uri("/api/v1/admin/compilers"))
+        .send_request(&app)
+        .await;
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body, json!({ "compilers": [] }));
+
+    let resp = authorized(TestRequest::post().uri("/api/v1/admin/compilers/refresh"))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body, json!({ "versions": 2 }));
+}
+
+#[actix_rt::test]
+async fn prefetch_at_startup() {
+    let (_mock_server, app_router) = admin_app_router(Some(TOKEN), &["0.8.13", "0.9.0"]).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = authorized(TestRequest::get().uri("/api/v1/admin/compilers"))
+        .send_request(&app)
+        .await;
+    let body: Value = test::read_body_json(resp).await;
+    let compilers = body["compilers"].as_array().unwrap();
+    assert_eq!(compilers.len(), 1);
+    assert_eq!(compilers[0]["version"], "v0.8.13+commit.abaa5c0e");
+}
+
+#[actix_rt::test]
+async fn disabled_without_token() {
+    let (_mock_server, app_router) = admin_app_router(None, &[]).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = authorized(TestRequest::get().uri("/api/v1/admin/compilers"))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,275 @@
+use actix_web::{
+    http::StatusCode,
+    test::{self, TestRequest},
+    App,
+};
+use serde_json::json;
+use std::sync::Arc;
+use verification::{
+    configure_router, ApiError, AppRouter, Config, ErrorCode, VerificationResponse,
+};
+use wiremock::{
+    http::Method,
+    matchers::{method, path},
+    Mock, MockServer, Request, ResponseTemplate,
+};
+
+async fn sourcify_app_router(sourcify_response: ResponseTemplate) -> (MockServer, AppRouter) {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(sourcify_response)
+        .mount(&mock_server)
+        .await;
+    let app_router = app_router(&mock_server).await;
+    (mock_server, app_router)
+}
+
+async fn app_router(mock_server: &MockServer) -> AppRouter {
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    config.sourcify.api_url = mock_server.uri().parse().unwrap();
+    config.sourcify.initial_retry_delay_ms = 10;
+    config.sourcify.retry_deadline = 5;
+    AppRouter::new(config)
+        .await
+        .expect("couldn't initialize the app")
+}
+
+/// Requests to Sourcify verification endpoint received by the mock server
+async fn verification_requests(mock_server: &MockServer) -> Vec<Request> {
+    mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.method == Method::Post)
+        .collect()
+}
+
+fn request_body() -> serde_json::Value {
+    json!({
+        "address": "0x1234567890123456789012345678901234567890",
+        "chain": "77",
+        "files": {},
+    })
+}
+
+#[actix_rt::test]
+async fn malformed_request() {
+    let (_mock_server, app_router) = sourcify_app_router(ResponseTemplate::new(200)).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/verify")
+        .set_json(json!({ "address": "0x1234567890123456789012345678901234567890" }))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: ApiError = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::InvalidRequest);
+}
+
+#[actix_rt::test]
+async fn sourcify_validation_errors() {
+    let (_mock_server, app_router) =
+        sourcify_app_router(ResponseTemplate::new(400).set_body_json(json!({
+            "message": "Validation Error",
+            "errors": [{ "field": "chain", "message": "Invalid chain" }],
+        })))
+        .await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/verify")
+        .set_json(request_body())
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: ApiError = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::SourcifyValidationFailed);
+    assert!(
+        body.message.contains("Invalid chain"),
+        "body message: {}",
+        body.message
+    );
+}
+
+#[actix_rt::test]
+async fn sourcify_verification_failure() {
+    let (mock_server, app_router) = sourcify_app_router(
+        ResponseTemplate::new(400).set_body_json(json!({ "error": "Metadata file not found" })),
+    )
+    .await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/verify")
+        .set_json(request_body())
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: VerificationResponse = test::read_body_json(resp).await;
+    assert_eq!(body.code, Some(ErrorCode::SourcifyVerificationFailed));
+    assert_eq!(body.message, "Metadata file not found");
+    // client errors are not retried
+    assert_eq!(verification_requests(&mock_server).await.len(), 1);
+}
+
+#[actix_rt::test]
+async fn retries_sourcify_server_errors() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(400).set_body_json(json!({ "error": "Metadata file not found" })),
+        )
+        .mount(&mock_server)
+        .await;
+    let app_router = Arc::new(app_router(&mock_server).await);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/verify")
+        .set_json(request_body())
+        .send_request(&app)
+        .await;
+
+    let body: VerificationResponse = test::read_body_json(resp).await;
+    assert_eq!(body.code, Some(ErrorCode::SourcifyVerificationFailed));
+    assert_eq!(verification_requests(&mock_server).await.len(), 3);
+}
+
+#[actix_rt::test]
+async fn does_not_retry_possibly_processed_verifications() {
+    let (mock_server, app_router) = sourcify_app_router(ResponseTemplate::new(500)).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/verify")
+        .set_json(request_body())
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body: ApiError = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::SourcifyUnavailable);
+    // the verification may have been processed, so it's not repeated
+    assert_eq!(verification_requests(&mock_server).await.len(), 1);
+}
+
+#[actix_rt::test]
+async fn gives_up_when_retry_after_exceeds_deadline() {
+    let (mock_server, app_router) =
+        sourcify_app_router(ResponseTemplate::new(429).insert_header("Retry-After", "60")).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/verify")
+        .set_json(request_body())
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body: ApiError = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::SourcifyUnavailable);
+    assert_eq!(verification_requests(&mock_server).await.len(), 1);
+}
+
+#[actix_rt::test]
+async fn resolves_and_validates_chains() {
+    let (mock_server, app_router) = sourcify_app_router(
+        ResponseTemplate::new(400).set_body_json(json!({ "error": "Metadata file not found" })),
+    )
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/chains"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "name": "Sokol", "chainId": 77, "supported": true },
+            { "name": "Ethereum Mainnet", "chainId": 1, "supported": false },
+        ])))
+        .mount(&mock_server)
+        .await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    // not supported by Sourcify, missing from the list, unknown name
+    for chain in ["eth/mainnet", "12345", "unknown/network"] {
+        let mut body = request_body();
+        body["chain"] = chain.into();
+        let resp = TestRequest::post()
+            .uri("/api/v1/sourcify/verify")
+            .set_json(body)
+            .send_request(&app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: ApiError = test::read_body_json(resp).await;
+        assert_eq!(body.code, ErrorCode::UnsupportedChain, "{}", body.message);
+    }
+    assert!(verification_requests(&mock_server).await.is_empty());
+
+    // the name is replaced by the chain id before calling Sourcify
+    let mut body = request_body();
+    body["chain"] = "poa/sokol".into();
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/verify")
+        .set_json(body)
+        .send_request(&app)
+        .await;
+    let body: VerificationResponse = test::read_body_json(resp).await;
+    assert_eq!(body.code, Some(ErrorCode::SourcifyVerificationFailed));
+    let requests = verification_requests(&mock_server).await;
+    let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(sent["chain"], "77");
+}
+
+#[actix_rt::test]
+async fn caches_unavailable_chains() {
+    let (mock_server, app_router) = sourcify_app_router(
+        ResponseTemplate::new(400).set_body_json(json!({ "error": "Metadata file not found" })),
+    )
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/chains"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&mock_server)
+        .await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    // the chains are passed to Sourcify as is while the list is unavailable
+    for _ in 0..2 {
+        let resp = TestRequest::post()
+            .uri("/api/v1/sourcify/verify")
+            .set_json(request_body())
+            .send_request(&app)
+            .await;
+        let body: VerificationResponse = test::read_body_json(resp).await;
+        assert_eq!(body.code, Some(ErrorCode::SourcifyVerificationFailed));
+    }
+
+    // the list is requested once, without retries
+    let chains_requests = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.url.path() == "/chains")
+        .count();
+    assert_eq!(chains_requests, 1);
+}
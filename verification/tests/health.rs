@@ -0,0 +1,160 @@
+use actix_web::{
+    test::{self, TestRequest},
+    App,
+};
+use std::sync::Arc;
+use verification::{configure_router, AppRouter, Config};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+async fn sourcify_app_router(sourcify_status: u16) -> (MockServer, AppRouter) {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/health"))
+        .respond_with(ResponseTemplate::new(sourcify_status))
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    config.sourcify.api_url = mock_server.uri().parse().unwrap();
+    let app_router = AppRouter::new(config)
+        .await
+        .expect("couldn't initialize the app");
+    (mock_server, app_router)
+}
+
+#[actix_rt::test]
+async fn liveness() {
+    let (_mock_server, app_router) = sourcify_app_router(500).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get().uri("/health").send_request(&app).await;
+    assert!(
+        resp.status().is_success(),
+        "liveness should not depend on subsystems, status is {}",
+        resp.status()
+    );
+}
+
+#[actix_rt::test]
+async fn ready_when_sourcify_is_reachable() {
+    let (_mock_server, app_router) = sourcify_app_router(200).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get().uri("/ready").send_request(&app).await;
+    assert!(
+        resp.status().is_success(),
+        "service should be ready, status is {}",
+        resp.status()
+    );
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "ready": true,
+            "subsystems": {
+                "sourcify": {
+                    "ready": true,
+                    "message": "Sourcify API is reachable",
+                }
+            }
+        })
+    );
+}
+
+#[actix_rt::test]
+async fn degraded_when_sourcify_is_unhealthy() {
+    let (mock_server, app_router) = sourcify_app_router(500).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    // Sourcify outage doesn't take the service out of rotation
+    let resp = TestRequest::get().uri("/ready").send_request(&app).await;
+    assert!(resp.status().is_success(), "status is {}", resp.status());
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["ready"], true);
+    assert_eq!(body["subsystems"]["sourcify"]["ready"], true);
+    assert_eq!(body["subsystems"]["sourcify"]["degraded"], true);
+
+    // the result of the check is cached between probes
+    TestRequest::get().uri("/ready").send_request(&app).await;
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1, "requests: {:?}", requests);
+}
+
+#[actix_rt::test]
+async fn starts_when_compilers_list_is_unavailable() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/list.json"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.sourcify.enabled = false;
+    config.solidity.compilers_lists[0].url =
+        format!("{}/list.json", mock_server.uri()).parse().unwrap();
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("app should start without compilers list"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get().uri("/ready").send_request(&app).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    // depending on whether the list was persisted by previous runs,
+    // the service either uses the last known list or has no compilers at all
+    let status = &body["subsystems"]["compilers_list"];
+    assert!(
+        status["degraded"] == true || status["ready"] == false,
+        "compilers list status: {}",
+        status
+    );
+}
+
+#[actix_rt::test]
+async fn reports_background_jobs() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/list.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"builds": []}"#))
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.sourcify.enabled = false;
+    config.solidity.compilers_lists[0].url =
+        format!("{}/list.json", mock_server.uri()).parse().unwrap();
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get().uri("/jobs").send_request(&app).await;
+    assert!(resp.status().is_success(), "status is {}", resp.status());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let jobs = body["jobs"].as_array().expect("jobs should be a list");
+    assert_eq!(jobs.len(), 1, "jobs: {:?}", jobs);
+    let job = &jobs[0];
+    assert_eq!(job["name"], "refresh compiler versions");
+    assert_eq!(job["running"], false);
+    assert_eq!(job["last_run"], serde_json::Value::Null);
+    assert!(job["next_run"].is_string(), "job: {}", job);
+
+    // jobs are not scheduled after the shutdown
+    app_router.shutdown().await;
+    let resp = TestRequest::get().uri("/jobs").send_request(&app).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["jobs"][0]["next_run"], serde_json::Value::Null);
+}
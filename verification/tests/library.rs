@@ -0,0 +1,65 @@
+use const_format::concatcp;
+use ethers_solc::{artifacts::Source, CompilerInput};
+use std::{collections::BTreeMap, env::temp_dir, path::PathBuf, str::FromStr};
+use verification::{
+    compiler::{CompilerVersion, Compilers, CompilersError},
+    solidity::{self, InitializationError, Input, LocalFetcher},
+};
+
+const DEPLOYED_BYTECODE: &str = concatcp!(
+    "0x6080604052600080fdfe",
+    "a2646970667358221220eb23ce2c13ea8739368f952f6c6a4b1f0623d147d2a19b6d4d26a61ab03fcd3e64736f6c634300080e0033"
+);
+const CREATION_TX_INPUT: &str = concatcp!(
+    "0x6080604052348015600f57600080fd5b50",
+    DEPLOYED_BYTECODE.split_at(2).1
+);
+
+fn compiler_input() -> CompilerInput {
+    CompilerInput {
+        language: "Solidity".to_string(),
+        sources: BTreeMap::from([(
+            PathBuf::from("source.sol"),
+            Source {
+                content: "pragma solidity ^0.8.0; contract A {}".to_string(),
+            },
+        )]),
+        settings: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn verifies_without_server() {
+    let folder = temp_dir().join("blockscout/verification/library");
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+    let compilers = Compilers::new(LocalFetcher::new(folder));
+    let input = |creation_tx_input| Input {
+        compiler_version: CompilerVersion::from_str("v0.8.14+commit.80d49f37").unwrap(),
+        compiler_input: compiler_input(),
+        creation_tx_input,
+        deployed_bytecode: DEPLOYED_BYTECODE,
+        bruteforce_bytecode_hashes: false,
+    };
+
+    let err = solidity::verify(&compilers, input("0xzz"))
+        .await
+        .expect_err("creation input is invalid");
+    assert!(
+        matches!(
+            err,
+            solidity::Error::Initialization(InitializationError::InvalidCreationTxInput(_))
+        ),
+        "{:?}",
+        err
+    );
+
+    let err = solidity::verify(&compilers, input(CREATION_TX_INPUT))
+        .await
+        .expect_err("compiler is missing");
+    assert!(
+        matches!(err, solidity::Error::Compilers(CompilersError::Fetch(_))),
+        "{:?}",
+        err
+    );
+}
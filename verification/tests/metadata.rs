@@ -0,0 +1,74 @@
+use actix_web::{
+    http::StatusCode,
+    test::{self, TestRequest},
+    App,
+};
+use const_format::concatcp;
+use serde_json::json;
+use std::{env::temp_dir, sync::Arc};
+use verification::{configure_router, ApiError, AppRouter, Config, ErrorCode, FetcherKind};
+
+const DEPLOYED_BYTECODE: &str = concatcp!(
+    "0x6080604052600080fdfe",
+    "a2646970667358221220eb23ce2c13ea8739368f952f6c6a4b1f0623d147d2a19b6d4d26a61ab03fcd3e64736f6c634300080e0033"
+);
+const CREATION_TX_INPUT: &str = concatcp!(
+    "0x6080604052348015600f57600080fd5b50",
+    DEPLOYED_BYTECODE.split_at(2).1
+);
+
+async fn metadata_app_router() -> AppRouter {
+    let folder = temp_dir().join("blockscout/verification/metadata");
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+
+    let mut config = Config::default();
+    config.sourcify.enabled = false;
+    config.solidity.fetcher = FetcherKind::Local;
+    config.solidity.compilers_folder = folder;
+    AppRouter::new(config)
+        .await
+        .expect("couldn't initialize the app")
+}
+
+#[actix_rt::test]
+async fn verifies_using_metadata() {
+    let app_router = Arc::new(metadata_app_router().await);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let metadata = include_str!("contracts/storage/metadata.json");
+    let source = include_str!("contracts/storage/source.sol");
+    for (files, code) in [
+        (json!({ "source.sol": source }), ErrorCode::InvalidMetadata),
+        (
+            json!({ "1_Storage.sol": "modified", "metadata.json": metadata }),
+            ErrorCode::InvalidMetadata,
+        ),
+        // the metadata is valid, so the exact compiler is requested
+        (
+            json!({ "1_Storage.sol": source, "metadata.json": metadata }),
+            ErrorCode::CompilerNotFound,
+        ),
+    ] {
+        let resp = TestRequest::post()
+            .uri("/api/v1/solidity/verify/metadata")
+            .set_json(json!({
+                "creation_bytecode": CREATION_TX_INPUT,
+                "deployed_bytecode": DEPLOYED_BYTECODE,
+                "files": files,
+            }))
+            .send_request(&app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: ApiError = test::read_body_json(resp).await;
+        assert_eq!(body.code, code, "{}", body.message);
+        if code == ErrorCode::CompilerNotFound {
+            assert!(
+                body.message.contains("0.8.7+commit.e28d00a7"),
+                "{}",
+                body.message
+            );
+        }
+    }
+}
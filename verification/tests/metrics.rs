@@ -0,0 +1,66 @@
+use actix_web::{
+    test::{self, TestRequest},
+    App,
+};
+use serde_json::json;
+use std::{str::from_utf8, sync::Arc};
+use verification::{configure_router, AppRouter, Config};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+#[actix_rt::test]
+async fn exposes_metrics() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(400).set_body_json(json!({ "error": "Metadata file not found" })),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    config.sourcify.api_url = mock_server.uri().parse().unwrap();
+    config.sourcify.initial_retry_delay_ms = 10;
+    config.sourcify.retry_deadline = 5;
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    // failed verification reaches the handler and should be counted
+    TestRequest::post()
+        .uri("/api/v1/sourcify/verify")
+        .set_json(json!({
+            "address": "0x1234567890123456789012345678901234567890",
+            "chain": "77",
+            "files": {},
+        }))
+        .send_request(&app)
+        .await;
+
+    let resp = TestRequest::get().uri("/metrics").send_request(&app).await;
+    assert!(
+        resp.status().is_success(),
+        "failed to get metrics, status is {}",
+        resp.status()
+    );
+
+    let body = test::read_body(resp).await;
+    let body = from_utf8(&body).expect("metrics should be valid utf-8");
+    assert!(
+        body.contains(r#"verification_requests_total{route="sourcify",status="failed"} 1"#),
+        "metrics do not contain the failed verification request: {}",
+        body
+    );
+    assert!(
+        body.contains("verification_compiler_download_failures_total 0"),
+        "metrics do not contain compiler download failures: {}",
+        body
+    );
+}
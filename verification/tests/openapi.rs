@@ -0,0 +1,77 @@
+use actix_web::{
+    test::{self, TestRequest},
+    App,
+};
+use std::{path::PathBuf, sync::Arc};
+use verification::{configure_router, AppRouter, Config};
+
+const SPEC_PATH: &str = "openapi.json";
+
+/// Set the variable to regenerate the committed specification
+/// instead of comparing against it.
+const UPDATE_ENV: &str = "UPDATE_OPENAPI";
+
+#[actix_rt::test]
+async fn openapi_spec_is_up_to_date() {
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get()
+        .uri("/openapi.json")
+        .send_request(&app)
+        .await;
+    assert!(
+        resp.status().is_success(),
+        "Invalid status code (success expected): {}",
+        resp.status()
+    );
+    let spec: serde_json::Value = test::read_body_json(resp).await;
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(SPEC_PATH);
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        let content = serde_json::to_string_pretty(&spec).unwrap() + "\n";
+        std::fs::write(&path, content).expect("couldn't write the specification");
+        return;
+    }
+
+    let committed: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(&path).expect("couldn't read the committed specification"),
+    )
+    .expect("committed specification is not a valid json");
+    assert_eq!(
+        spec, committed,
+        "OpenAPI specification has changed; run the test with `{}=1` to update {}",
+        UPDATE_ENV, SPEC_PATH
+    );
+}
+
+#[actix_rt::test]
+async fn swagger_ui() {
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get()
+        .uri("/swagger-ui")
+        .send_request(&app)
+        .await;
+    assert!(
+        resp.status().is_success(),
+        "Invalid status code (success expected): {}",
+        resp.status()
+    );
+    let body = test::read_body(resp).await;
+    let body = std::str::from_utf8(&body).unwrap();
+    assert!(body.contains("/openapi.json"), "body: {}", body);
+}
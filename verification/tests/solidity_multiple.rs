@@ -14,11 +14,12 @@ use std::{
     str::{from_utf8, FromStr},
 };
 use verification::{
-    configure_router, AppRouter, Config, DisplayBytes, VerificationResponse, VerificationStatus,
+    configure_router, AppRouter, Config, DisplayBytes, ErrorCode, VerificationResponse,
+    VerificationStatus,
 };
 
-const CONTRACTS_DIR: &'static str = "tests/contracts";
-const ROUTE: &'static str = "/api/v1/solidity/verify/multiple-files";
+const CONTRACTS_DIR: &str = "tests/contracts";
+const ROUTE: &str = "/api/v1/solidity/verify/multiple-files";
 
 async fn global_app_router() -> &'static AppRouter {
     static APP_ROUTER: OnceCell<AppRouter> = OnceCell::new();
@@ -170,7 +171,12 @@ async fn test_success(dir: &'static str, mut input: TestInput) {
 }
 
 /// Test verification failures (note: do not handle 400 BadRequest responses)
-async fn test_failure<'a>(dir: &'static str, mut input: TestInput, expected_message: &'a str) {
+async fn test_failure(
+    dir: &'static str,
+    mut input: TestInput,
+    expected_code: ErrorCode,
+    expected_message: &str,
+) {
     let (response, _expected_constructor_argument) = test_setup(dir, &mut input).await;
 
     assert!(
@@ -193,6 +199,13 @@ async fn test_failure<'a>(dir: &'static str, mut input: TestInput, expected_mess
         "Failure verification result should be None"
     );
 
+    assert_eq!(
+        verification_response.code,
+        Some(expected_code),
+        "Invalid error code. Response: {:?}",
+        verification_response
+    );
+
     assert!(
         verification_response.message.contains(expected_message),
         "Invalid message: {}",
@@ -286,6 +299,7 @@ mod error_tests {
         test_failure(
             contract_dir,
             test_input,
+            ErrorCode::BytecodeMismatch,
             "No contract could be verified with provided data",
         )
         .await;
@@ -296,7 +310,13 @@ mod error_tests {
         let contract_dir = "simple_storage";
         let test_input = TestInput::new("SimpleStorage", "v0.4.24+commit.e67f0147")
             .with_source_code("pragma solidity ^0.4.24; contract SimpleStorage { ".to_string());
-        test_failure(contract_dir, test_input, "ParserError").await;
+        test_failure(
+            contract_dir,
+            test_input,
+            ErrorCode::CompilationFailed,
+            "ParserError",
+        )
+        .await;
     }
 }
 
@@ -398,7 +418,7 @@ mod tests_from_constructor_arguments_test_exs {
     #[actix_rt::test]
     async fn verifies_with_string_in_keccak256() {
         let contract_dir = "ERC677";
-        let mut test_input = TestInput::new("ERC677MultiBridgeToken", "v0.5.10+commit.5a6ea5b1")
+        let test_input = TestInput::new("ERC677MultiBridgeToken", "v0.5.10+commit.5a6ea5b1")
             .with_optimization_runs(200)
             .has_constructor_args();
         test_success(contract_dir, test_input).await;
@@ -1,11 +1,20 @@
 use std::sync::Arc;
 
 use actix_web::{
+    http::StatusCode,
     test::{self, TestRequest},
     App,
 };
+use ethers_core::{types::H256, utils::keccak256};
 use serde_json::json;
-use verification::{configure_router, AppRouter, Config, VerificationResponse, VerificationStatus};
+use verification::{
+    configure_router, ApiError, AppRouter, Config, ErrorCode, MatchType, VerificationResponse,
+    VerificationStatus,
+};
+use wiremock::{
+    matchers::{body_partial_json, header, method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
 
 #[actix_rt::test]
 async fn should_return_200() {
@@ -16,7 +25,7 @@ async fn should_return_200() {
             .await
             .expect("couldn't initialize the app"),
     );
-    let mut app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
 
     let metadata = include_str!("contracts/storage/metadata.json");
     let source = include_str!("contracts/storage/source.sol");
@@ -34,7 +43,7 @@ async fn should_return_200() {
     let resp = TestRequest::post()
         .uri("/api/v1/sourcify/verify")
         .set_json(&request_body)
-        .send_request(&mut app)
+        .send_request(&app)
         .await;
 
     assert!(
@@ -43,7 +52,13 @@ async fn should_return_200() {
         resp.status()
     );
 
-    let body: serde_json::Value = test::read_body_json(resp).await;
+    let mut body: serde_json::Value = test::read_body_json(resp).await;
+    // the contract had been verified before, so the storage time is returned
+    let storage_timestamp = body["result"]
+        .as_object_mut()
+        .unwrap()
+        .remove("storage_timestamp");
+    assert!(storage_timestamp.unwrap().is_string());
     assert_eq!(
         body,
         serde_json::json!({
@@ -59,8 +74,9 @@ async fn should_return_200() {
                 "contract_libraries": {},
                 "abi": "[{\"inputs\":[],\"name\":\"retrieve\",\"outputs\":[{\"internalType\":\"uint256\",\"name\":\"\",\"type\":\"uint256\"}],\"stateMutability\":\"view\",\"type\":\"function\"},{\"inputs\":[{\"internalType\":\"uint256\",\"name\":\"num\",\"type\":\"uint256\"}],\"name\":\"store\",\"outputs\":[],\"stateMutability\":\"nonpayable\",\"type\":\"function\"}]",
                 "sources": {
-                    "1_Storage.sol": "// SPDX-License-Identifier: GPL-3.0\n\npragma solidity >=0.7.0 <0.9.0;\n\n/**\n * @title Storage\n * @dev Store & retrieve value in a variable\n * @custom:dev-run-script ./scripts/deploy_with_ethers.ts\n */\ncontract Storage {\n\n    uint256 number;\n\n    /**\n     * @dev Store value in variable\n     * @param num value to store\n     */\n    function store(uint256 num) public {\n        number = num;\n    }\n\n    /**\n     * @dev Return value \n     * @return value of 'number'\n     */\n    function retrieve() public view returns (uint256){\n        return number;\n    }\n}"
-                }
+                    "contracts/1_Storage.sol": "// SPDX-License-Identifier: GPL-3.0\n\npragma solidity >=0.7.0 <0.9.0;\n\n/**\n * @title Storage\n * @dev Store & retrieve value in a variable\n * @custom:dev-run-script ./scripts/deploy_with_ethers.ts\n */\ncontract Storage {\n\n    uint256 number;\n\n    /**\n     * @dev Store value in variable\n     * @param num value to store\n     */\n    function store(uint256 num) public {\n        number = num;\n    }\n\n    /**\n     * @dev Return value \n     * @return value of 'number'\n     */\n    function retrieve() public view returns (uint256){\n        return number;\n    }\n}"
+                },
+                "match_type": "perfect"
             },
             "status": "0"
         }),
@@ -76,7 +92,7 @@ async fn invalid_contracts() {
             .await
             .expect("couldn't initialize the app"),
     );
-    let mut app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
 
     let metadata_content = include_str!("contracts/storage/metadata.json");
     let source = include_str!("contracts/storage/source.sol");
@@ -118,7 +134,7 @@ async fn invalid_contracts() {
         let resp = TestRequest::post()
             .uri("/api/v1/sourcify/verify")
             .set_json(&request_body)
-            .send_request(&mut app)
+            .send_request(&app)
             .await;
 
         let body: VerificationResponse = test::read_body_json(resp).await;
@@ -133,3 +149,451 @@ async fn invalid_contracts() {
         );
     }
 }
+
+#[actix_rt::test]
+async fn reports_partial_match() {
+    let address = "0x1277E7D253e0c073418B986b8228BF282554cA5e";
+    let metadata = include_str!("contracts/storage/metadata.json");
+    let source = include_str!("contracts/storage/source.sol");
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": [{
+                "address": address,
+                "status": "partial",
+                "storageTimestamp": "2022-07-01T12:00:00.000Z",
+            }]
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/files/partial/77/{}", address)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "files": [
+                { "name": "metadata.json", "content": metadata },
+                { "name": "1_Storage.sol", "content": source },
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    config.sourcify.api_url = mock_server.uri().parse().unwrap();
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/verify")
+        .set_json(json!({
+            "address": address,
+            "chain": "77",
+            "files": {
+                "source.sol": source,
+                "metadata.json": metadata,
+            }
+        }))
+        .send_request(&app)
+        .await;
+
+    let body: VerificationResponse = test::read_body_json(resp).await;
+    assert_eq!(body.status, VerificationStatus::Ok, "{}", body.message);
+    let result = body.result.unwrap();
+    assert_eq!(result.match_type, Some(MatchType::Partial));
+    assert_eq!(
+        result.storage_timestamp.as_deref(),
+        Some("2022-07-01T12:00:00.000Z")
+    );
+}
+
+#[actix_rt::test]
+async fn checks_and_imports_verified_contracts() {
+    let verified = "0x1277E7D253e0c073418B986b8228BF282554cA5e";
+    let not_verified = "0x1234567890123456789012345678901234567890";
+    let metadata = include_str!("contracts/storage/metadata.json");
+    let source = include_str!("contracts/storage/source.sol");
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/check-by-addresses"))
+        .and(query_param("chainIds", "77"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "address": verified, "status": "perfect", "chainIds": ["77"] },
+            { "address": not_verified, "status": "false" },
+        ])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/files/full/77/{}", verified)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "files": [
+                { "name": "metadata.json", "content": metadata },
+                { "name": "1_Storage.sol", "content": source },
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    config.sourcify.api_url = mock_server.uri().parse().unwrap();
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get()
+        .uri(&format!(
+            "/api/v1/sourcify/check?chain=77&addresses={},{}",
+            verified, not_verified
+        ))
+        .send_request(&app)
+        .await;
+    assert!(resp.status().is_success(), "status is {}", resp.status());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body,
+        json!({
+            "contracts": [
+                { "address": verified, "match_type": "perfect" },
+                { "address": not_verified, "match_type": null },
+            ]
+        })
+    );
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/import")
+        .set_json(json!({ "address": verified, "chain": "77" }))
+        .send_request(&app)
+        .await;
+    let body: VerificationResponse = test::read_body_json(resp).await;
+    assert_eq!(body.status, VerificationStatus::Ok, "{}", body.message);
+    let result = body.result.unwrap();
+    assert_eq!(result.contract_name, "Storage");
+    assert_eq!(result.match_type, Some(MatchType::Perfect));
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/import")
+        .set_json(json!({ "address": not_verified, "chain": "77" }))
+        .send_request(&app)
+        .await;
+    let body: VerificationResponse = test::read_body_json(resp).await;
+    assert_eq!(body.code, Some(ErrorCode::SourcifyContractNotVerified));
+}
+
+#[actix_rt::test]
+async fn imports_contracts_with_repository_files() {
+    let address = "0x1277E7D253e0c073418B986b8228BF282554cA5e";
+    let hash = |content: &str| format!("{:?}", H256::from(keccak256(content)));
+    let metadata = json!({
+        "compiler": { "version": "0.8.14+commit.80d49f37" },
+        "language": "Solidity",
+        "output": { "abi": [] },
+        "settings": {
+            "compilationTarget": { "contracts/a/Token.sol": "A" },
+            "libraries": { "contracts/Lib.sol:Lib": "0xcafecafecafecafecafecafecafecafecafecafe" },
+            "optimizer": { "enabled": false, "runs": 200 },
+        },
+        "sources": {
+            "contracts/a/Token.sol": { "keccak256": hash("contract A {}") },
+            "contracts/b/Token.sol": { "keccak256": hash("contract B {}") },
+        },
+        "version": 1,
+    });
+    let folder = format!("/repository/contracts/full_match/77/{}", address);
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/check-by-addresses"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "address": address, "status": "perfect", "chainIds": ["77"] },
+        ])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/files/full/77/{}", address)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "files": [
+                {
+                    "name": "metadata.json",
+                    "path": format!("{}/metadata.json", folder),
+                    "content": metadata.to_string(),
+                },
+                {
+                    "name": "constructor-args.txt",
+                    "path": format!("{}/constructor-args.txt", folder),
+                    "content": "0x0000000000000000000000000000000000000000000000000000000000000fff",
+                },
+                {
+                    "name": "library-map.json",
+                    "path": format!("{}/library-map.json", folder),
+                    "content": "{}",
+                },
+                {
+                    "name": "creator-tx-hash.txt",
+                    "path": format!("{}/creator-tx-hash.txt", folder),
+                    "content": "0x01",
+                },
+                {
+                    "name": "Token.sol",
+                    "path": format!("{}/sources/contracts/a/Token.sol", folder),
+                    "content": "contract A {}",
+                },
+                {
+                    "name": "Token.sol",
+                    "path": format!("{}/sources/contracts/b/Token.sol", folder),
+                    "content": "contract B {}",
+                },
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    config.sourcify.api_url = mock_server.uri().parse().unwrap();
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/import")
+        .set_json(json!({ "address": address, "chain": "77" }))
+        .send_request(&app)
+        .await;
+    let body: VerificationResponse = test::read_body_json(resp).await;
+    assert_eq!(body.status, VerificationStatus::Ok, "{}", body.message);
+    let result = body.result.unwrap();
+    assert_eq!(result.contract_name, "A");
+    assert_eq!(
+        result.sources,
+        [
+            (
+                "contracts/a/Token.sol".to_string(),
+                "contract A {}".to_string()
+            ),
+            (
+                "contracts/b/Token.sol".to_string(),
+                "contract B {}".to_string()
+            ),
+        ]
+        .into_iter()
+        .collect()
+    );
+}
+
+#[actix_rt::test]
+async fn verifies_contracts_in_session() {
+    let address = "0x1277E7D253e0c073418B986b8228BF282554cA5e";
+    let session_contract = |status: Option<&str>| {
+        json!({
+            "verificationId": "0x1234",
+            "name": "Storage",
+            "compiledPath": "contracts/1_Storage.sol",
+            "address": status.map(|_| address),
+            "chainId": status.map(|_| "77"),
+            "status": status,
+            "files": {
+                "found": ["contracts/1_Storage.sol"],
+                "missing": status.map_or(json!({ "contracts/Lib.sol": {} }), |_| json!({})),
+            },
+        })
+    };
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/session/input-files"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Set-Cookie", "sourcify_vid=secret; Path=/; HttpOnly")
+                .set_body_json(json!({
+                    "contracts": [session_contract(None)],
+                    "unused": ["README.md"],
+                })),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/session/verify-validated"))
+        .and(header("cookie", "sourcify_vid=secret"))
+        .and(body_partial_json(json!({
+            "contracts": [{ "verificationId": "0x1234", "address": address, "chainId": "77" }]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "contracts": [session_contract(Some("perfect"))],
+            "unused": [],
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/session/clear"))
+        .and(header("cookie", "sourcify_vid=secret"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    config.sourcify.api_url = mock_server.uri().parse().unwrap();
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/session")
+        .set_json(json!({ "files": { "metadata.json": "{}", "README.md": "" } }))
+        .send_request(&app)
+        .await;
+    assert!(resp.status().is_success(), "status is {}", resp.status());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+    assert_eq!(
+        body["contracts"][0]["missing_files"],
+        json!(["contracts/Lib.sol"])
+    );
+    assert_eq!(body["unused_files"], json!(["README.md"]));
+
+    let resp = TestRequest::post()
+        .uri(&format!("/api/v1/sourcify/session/{}/verify", session_id))
+        .set_json(json!({
+            "contracts": [{ "verification_id": "0x1234", "address": address, "chain": "poa/sokol" }]
+        }))
+        .send_request(&app)
+        .await;
+    assert!(resp.status().is_success(), "status is {}", resp.status());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["contracts"][0]["match_type"], "perfect");
+    assert_eq!(body["contracts"][0]["address"], address);
+
+    let resp = TestRequest::delete()
+        .uri(&format!("/api/v1/sourcify/session/{}", session_id))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let resp = TestRequest::get()
+        .uri(&format!("/api/v1/sourcify/session/{}", session_id))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let body: ApiError = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::SessionNotFound);
+}
+
+#[actix_rt::test]
+async fn limits_sessions() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/session/input-files"))
+        .and(body_partial_json(
+            json!({ "files": { "metadata.json": "invalid" } }),
+        ))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": "Invalid metadata.json"
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/session/input-files"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Set-Cookie", "sourcify_vid=secret; Path=/; HttpOnly")
+                .set_body_json(json!({ "contracts": [], "unused": [] })),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    config.sourcify.api_url = mock_server.uri().parse().unwrap();
+    config.sourcify.max_sessions = 1;
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    // Sourcify doesn't open a session for invalid files, but its error is returned
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/session")
+        .set_json(json!({ "files": { "metadata.json": "invalid" } }))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: ApiError = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::SourcifyValidationFailed);
+    assert!(
+        body.message.contains("Invalid metadata.json"),
+        "{}",
+        body.message
+    );
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/session")
+        .set_json(json!({ "files": { "metadata.json": "{}" } }))
+        .send_request(&app)
+        .await;
+    assert!(resp.status().is_success(), "status is {}", resp.status());
+
+    let resp = TestRequest::post()
+        .uri("/api/v1/sourcify/session")
+        .set_json(json!({ "files": { "metadata.json": "{}" } }))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: ApiError = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::TooManySessions);
+}
+
+#[actix_rt::test]
+async fn reserves_sessions_before_opening() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/session/input-files"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Set-Cookie", "sourcify_vid=secret; Path=/; HttpOnly")
+                .set_body_json(json!({ "contracts": [], "unused": [] }))
+                .set_delay(std::time::Duration::from_millis(200)),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.solidity.enabled = false;
+    config.sourcify.api_url = mock_server.uri().parse().unwrap();
+    config.sourcify.max_sessions = 1;
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    // the second session is rejected while the first one is being opened,
+    // so Sourcify never opens a session the service can't keep
+    let create = || {
+        TestRequest::post()
+            .uri("/api/v1/sourcify/session")
+            .set_json(json!({ "files": { "metadata.json": "{}" } }))
+            .send_request(&app)
+    };
+    let (first, second) = futures::join!(create(), create());
+    let mut statuses = [first.status(), second.status()];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::SERVICE_UNAVAILABLE]);
+}
@@ -0,0 +1,255 @@
+use actix_web::{
+    http::StatusCode,
+    test::{self, TestRequest},
+    App,
+};
+use serde_json::{json, Value};
+use std::{env::temp_dir, path::PathBuf, sync::Arc};
+use verification::{configure_router, ApiError, AppRouter, Config, ErrorCode, FetcherKind};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const LIST_JSON: &str = r#"{
+    "builds": [
+        {
+            "path": "solc-linux-amd64-v0.8.15-nightly.2022.5.27+commit.095cc647",
+            "longVersion": "0.8.15-nightly.2022.5.27+commit.095cc647",
+            "sha256": "0x35708c1593f3daddae734065e361a839ee39d400825972fb3f50718495be82b1"
+        },
+        {
+            "path": "solc-linux-amd64-v0.8.14+commit.80d49f37",
+            "longVersion": "0.8.14+commit.80d49f37",
+            "sha256": "0xd5b027d86c9dc85b2a2ba2d4a2ee3cb2a9be3cf0d8e3a5ea0b2f03bc14f9e7bb"
+        },
+        {
+            "path": "solc-linux-amd64-v0.4.13+commit.0fb4cb1a",
+            "longVersion": "0.4.13+commit.0fb4cb1a",
+            "sha256": "0x791ee3a20adf6c5ab76cc889f13cca102f76eb0b7cf0da4a0b5b11dc46edf349"
+        }
+    ]
+}"#;
+
+async fn solidity_app_router() -> (MockServer, AppRouter) {
+    solidity_app_router_with_folder(Config::default().solidity.compilers_folder).await
+}
+
+async fn solidity_app_router_with_folder(compilers_folder: PathBuf) -> (MockServer, AppRouter) {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/list.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(LIST_JSON))
+        .mount(&mock_server)
+        .await;
+
+    let mut config = Config::default();
+    config.sourcify.enabled = false;
+    config.solidity.compilers_lists[0].url =
+        format!("{}/list.json", mock_server.uri()).parse().unwrap();
+    config.solidity.compilers_folder = compilers_folder;
+    let app_router = AppRouter::new(config)
+        .await
+        .expect("couldn't initialize the app");
+    (mock_server, app_router)
+}
+
+#[actix_rt::test]
+async fn filters_versions() {
+    let (_mock_server, app_router) = solidity_app_router().await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    for (query, expected) in [
+        (
+            "",
+            json!([
+                "v0.8.15-nightly.2022.5.27+commit.095cc647",
+                "v0.8.14+commit.80d49f37",
+                "v0.4.13+commit.0fb4cb1a",
+            ]),
+        ),
+        (
+            "?kind=release",
+            json!(["v0.8.14+commit.80d49f37", "v0.4.13+commit.0fb4cb1a"]),
+        ),
+        (
+            "?kind=nightly",
+            json!(["v0.8.15-nightly.2022.5.27+commit.095cc647"]),
+        ),
+        (
+            "?version=%5E0.8",
+            json!([
+                "v0.8.15-nightly.2022.5.27+commit.095cc647",
+                "v0.8.14+commit.80d49f37",
+            ]),
+        ),
+        (
+            "?kind=release&version=%3C0.5",
+            json!(["v0.4.13+commit.0fb4cb1a"]),
+        ),
+    ] {
+        let resp = TestRequest::get()
+            .uri(&format!("/api/v1/solidity/versions{}", query))
+            .send_request(&app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK, "query: {}", query);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({ "versions": expected }), "query: {}", query);
+    }
+}
+
+#[actix_rt::test]
+async fn returns_metadata() {
+    let (mock_server, app_router) = solidity_app_router().await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get()
+        .uri("/api/v1/solidity/versions?kind=nightly&metadata=true")
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body,
+        json!({
+            "versions": ["v0.8.15-nightly.2022.5.27+commit.095cc647"],
+            "metadata": [{
+                "version": "v0.8.15-nightly.2022.5.27+commit.095cc647",
+                "kind": "nightly",
+                "date": "2022-05-27",
+                "download_url": format!(
+                    "{}/solc-linux-amd64-v0.8.15-nightly.2022.5.27+commit.095cc647",
+                    mock_server.uri()
+                ),
+                "sha256": "0x35708c1593f3daddae734065e361a839ee39d400825972fb3f50718495be82b1",
+                "downloaded": false,
+            }],
+        })
+    );
+}
+
+#[actix_rt::test]
+async fn reports_compilers_downloaded_before_restart() {
+    let folder = tempfile::tempdir().unwrap();
+    let version = "v0.8.14+commit.80d49f37";
+    std::fs::create_dir_all(folder.path().join(version)).unwrap();
+    std::fs::write(folder.path().join(version).join("solc"), "compiler").unwrap();
+
+    let (_mock_server, app_router) =
+        solidity_app_router_with_folder(folder.path().to_path_buf()).await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get()
+        .uri("/api/v1/solidity/versions?kind=release&metadata=true")
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    let downloaded: Vec<_> = body["metadata"]
+        .as_array()
+        .expect("metadata should be returned")
+        .iter()
+        .map(|metadata| (metadata["version"].clone(), metadata["downloaded"].clone()))
+        .collect();
+    assert_eq!(
+        downloaded,
+        vec![
+            (json!(version), json!(true)),
+            (json!("v0.4.13+commit.0fb4cb1a"), json!(false)),
+        ]
+    );
+}
+
+#[actix_rt::test]
+async fn invalid_query() {
+    let (_mock_server, app_router) = solidity_app_router().await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    for query in ["?version=not-a-requirement", "?kind=unknown"] {
+        let resp = TestRequest::get()
+            .uri(&format!("/api/v1/solidity/versions{}", query))
+            .send_request(&app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "query: {}", query);
+        let body: ApiError = test::read_body_json(resp).await;
+        assert_eq!(body.code, ErrorCode::InvalidRequest, "query: {}", query);
+    }
+}
+
+#[actix_rt::test]
+async fn unresolved_compiler_versions() {
+    let (_mock_server, app_router) = solidity_app_router().await;
+    let app_router = Arc::new(app_router);
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    for (compiler_version, expected) in [
+        ("0.8", ErrorCode::InvalidCompilerVersion),
+        ("0.8.13", ErrorCode::CompilerNotFound),
+        // nightly builds are not used instead of missing releases
+        ("0.8.15", ErrorCode::CompilerNotFound),
+    ] {
+        let resp = TestRequest::post()
+            .uri("/api/v1/solidity/verify/multiple-files")
+            .set_json(json!({
+                "deployed_bytecode": "0x",
+                "creation_bytecode": "0x",
+                "compiler_version": compiler_version,
+                "sources": {},
+                "evm_version": "default",
+            }))
+            .send_request(&app)
+            .await;
+        assert_eq!(
+            resp.status(),
+            StatusCode::BAD_REQUEST,
+            "version: {}",
+            compiler_version
+        );
+        let body: ApiError = test::read_body_json(resp).await;
+        assert_eq!(body.code, expected, "version: {}", compiler_version);
+    }
+}
+
+#[actix_rt::test]
+async fn local_fetcher_versions() {
+    let folder = temp_dir().join("blockscout/verification/tests/local_compilers/");
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(folder.join("v0.8.14+commit.80d49f37")).unwrap();
+    std::fs::write(folder.join("v0.8.14+commit.80d49f37/solc"), "compiler").unwrap();
+
+    let mut config = Config::default();
+    config.sourcify.enabled = false;
+    config.solidity.fetcher = FetcherKind::Local;
+    config.solidity.compilers_folder = folder;
+    let app_router = Arc::new(
+        AppRouter::new(config)
+            .await
+            .expect("couldn't initialize the app"),
+    );
+    let app = test::init_service(App::new().configure(configure_router(&*app_router))).await;
+
+    let resp = TestRequest::get()
+        .uri("/api/v1/solidity/versions?metadata=true")
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body,
+        json!({
+            "versions": ["v0.8.14+commit.80d49f37"],
+            "metadata": [{
+                "version": "v0.8.14+commit.80d49f37",
+                "kind": "release",
+                "date": null,
+                "download_url": null,
+                "sha256": null,
+                "downloaded": true,
+            }],
+        })
+    );
+}